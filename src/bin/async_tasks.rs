@@ -0,0 +1,150 @@
+//! 异步任务
+//! LED 闪烁、按键和对射式红外传感器三个任务并发运行，由 SysTick、EXTI1、EXTI15_10 中断唤醒
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use core::future::Future;
+use core::pin::{pin, Pin};
+
+use stm32f1_core::executor::{self, timer::Timer};
+use stm32f1_core::hardware::exti::{self, ExtiPin};
+use stm32f1_core::hardware::gpio::{Gpioa, Gpiob};
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::{entry, exception};
+use stm32f1::stm32f103::{interrupt, CorePeripherals, Peripherals, EXTI};
+
+// 定义 LED 连接的引脚号（PA1）
+const LED_PIN: u16 = 1;
+// 定义按键连接的引脚号（PB1）
+const KEY_PIN: u16 = 1;
+// 定义传感器连接的引脚号（PB14）
+const SENSOR_PIN: u16 = 14;
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let gpioa = &dp.GPIOA;
+    let gpiob = &dp.GPIOB;
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let exti = &dp.EXTI;
+    let afio = &dp.AFIO;
+    let mut syst = cp.SYST;
+    let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 使能 APB2 时钟
+    rcc.apb2enr.modify(|_, w| {
+        w.iopaen()
+            .enabled() // GPIOA
+            .iopben()
+            .enabled() // GPIOB
+            .afioen()
+            .enabled() // AFIO
+    });
+
+    // LED
+    // 配置引脚为推挽输出模式
+    gpioa
+        .crl
+        .modify(|_, w| w.mode1().output50().cnf1().push_pull());
+
+    // KEY
+    // 配置引脚为上拉输入模式
+    gpiob
+        .crl
+        .modify(|_, w| w.mode1().input().cnf1().alt_push_pull());
+    gpiob.bsrr.write(|w| w.bs1().set_bit());
+
+    // 对射式红外传感器
+    // 配置引脚为上拉输入模式
+    gpiob
+        .crh
+        .modify(|_, w| w.mode14().input().cnf14().alt_push_pull());
+    gpiob.bsrr.write(|w| w.bs14().set_bit());
+
+    let led = Gpioa::new(gpioa, LED_PIN);
+    let mut key = Gpiob::new(gpiob, KEY_PIN);
+    let mut sensor = Gpiob::new(gpiob, SENSOR_PIN);
+    // 选择 EXTI 的触发源
+    key.make_interrupt_source(afio);
+    sensor.make_interrupt_source(afio);
+
+    // 配置 NVIC 以使能中断
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    set_priority(&mut nvic, key.interrupt(), 1, 0).unwrap();
+    set_priority(&mut nvic, sensor.interrupt(), 1, 1).unwrap();
+    unsafe {
+        NVIC::unmask(key.interrupt());
+        NVIC::unmask(sensor.interrupt());
+    }
+
+    // 1ms 节拍
+    executor::timer::start(&mut syst, 1000).unwrap();
+
+    let blink = pin!(blink_task(led));
+    let key = pin!(key_task(key, exti));
+    let sensor = pin!(sensor_task(sensor, exti));
+    let mut tasks: [Pin<&mut dyn Future<Output = ()>>; 3] = [blink, key, sensor];
+
+    println!("run...");
+    executor::run(&mut tasks);
+}
+
+/// LED 每 500ms 翻转一次
+async fn blink_task(led: Gpioa<'_>) {
+    loop {
+        led.toggle();
+        Timer::after_ms(500).await;
+    }
+}
+
+/// 按键按下（下降沿）后打印，并延时 20ms 消抖
+async fn key_task(mut key: Gpiob<'_>, exti: &EXTI) {
+    loop {
+        key.wait_for_falling_edge(exti).await;
+        println!("key...");
+        Timer::after_ms(20).await;
+    }
+}
+
+/// 对射式红外传感器被遮挡（上升沿）时计数
+async fn sensor_task(mut sensor: Gpiob<'_>, exti: &EXTI) {
+    let mut count: u32 = 0;
+    loop {
+        sensor.wait_for_rising_edge(exti).await;
+        count += 1;
+        println!("count: {:#?}", count);
+    }
+}
+
+#[exception]
+fn SysTick() {
+    executor::timer::on_tick();
+}
+
+#[interrupt]
+fn EXTI1() {
+    exti::on_interrupt();
+}
+
+#[interrupt]
+fn EXTI15_10() {
+    exti::on_interrupt();
+}
@@ -0,0 +1,87 @@
+//! 备份寄存器
+//! 在备份寄存器中保存启动次数和亮度设置，复位后保持；
+//! PC13（TAMPER）拉低时硬件清除所有备份寄存器
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::bkp::{Backup, Persist, TamperLevel};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 设置保存的起始寄存器序号（DR2）
+const SETTINGS_INDEX: usize = 1;
+
+/// 需要在复位之间保存的设置
+struct Settings {
+    boots: u32,
+    brightness: u8,
+}
+
+impl Persist for Settings {
+    const SIZE: usize = 5;
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf[..4].copy_from_slice(&self.boots.to_le_bytes());
+        buf[4] = self.brightness;
+    }
+
+    fn decode(buf: &[u8]) -> Option<Self> {
+        Some(Settings {
+            boots: u32::from_le_bytes(buf[..4].try_into().ok()?),
+            brightness: buf[4],
+        })
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let pwr = &dp.PWR;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    let mut backup = Backup::new(dp.BKP, rcc, pwr);
+    if backup.domain_lost() {
+        println!("backup domain lost");
+        backup.mark_valid();
+    }
+
+    let mut settings = backup.load(SETTINGS_INDEX).unwrap_or(Settings {
+        boots: 0,
+        brightness: 50,
+    });
+    settings.boots += 1;
+    backup.store(SETTINGS_INDEX, &settings).unwrap();
+    println!(
+        "boots: {}, brightness: {}",
+        settings.boots, settings.brightness
+    );
+
+    // 侵入检测：PC13 低电平有效
+    backup.enable_tamper(TamperLevel::Low, false);
+
+    println!("loop...");
+    loop {
+        if backup.is_tamper_event() {
+            println!("tamper! backup registers erased");
+            backup.clear_tamper();
+        }
+        delay_ms(&mut syst, 500);
+    }
+}
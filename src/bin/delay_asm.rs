@@ -3,6 +3,8 @@
 #![no_main]
 #![allow(clippy::empty_loop)]
 
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, delay};
+
 use defmt::println;
 use defmt_rtt as _;
 use panic_probe as _;
@@ -12,22 +14,31 @@ use stm32f1::stm32f103::{CorePeripherals, Peripherals};
 
 #[entry]
 fn main() -> ! {
-    let _dp = Peripherals::take().unwrap();
-    let _cp = CorePeripherals::take().unwrap();
+    let dp = Peripherals::take().unwrap();
+    let mut cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    println!("配置时钟树");
+    set_clock(rcc);
+
+    // set_clock 已更新延时使用的时钟频率
+    println!("sysclk: {:?}", delay::sysclk());
+
+    // 使用 DWT 校验每次循环迭代的周期数
+    let cycles = delay::verify(&mut cp.DCB, &mut cp.DWT);
+    println!("cycles per iteration: {:?}", cycles);
 
     loop {
         for i in 0..10 {
             println!("i={:?}", i);
             // 延时一秒
-            delay_s(1);
+            delay::delay_ms(1000);
         }
     }
 }
-
-/// 定义一个简单的延时函数，使用忙等待的方式
-/// 延时大约一秒钟
-pub fn delay_s(cycles: u32) {
-    for _ in 0..cycles * 8_000_000 {
-        cortex_m::asm::nop();
-    }
-}
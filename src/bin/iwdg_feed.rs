@@ -0,0 +1,67 @@
+//! 独立看门狗
+//! 启动时打印上次是否由看门狗复位；之后以 1 秒超时启动看门狗，主循环每 200ms 喂狗一次，
+//! 按住按键（PB1）模拟程序卡死，停止喂狗后约 1 秒系统复位
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::gpio::{set_pin_mode, Gpiob, PinMode, Port};
+use stm32f1_core::hardware::iwdg::{self, IndependentWatchdog};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 看门狗超时时间（毫秒）
+const TIMEOUT_MS: u32 = 1000;
+// 喂狗间隔（毫秒），LSI 误差较大，取超时时间的 1/5
+const FEED_INTERVAL_MS: u32 = 200;
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let gpiob = &dp.GPIOB;
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 检查复位原因
+    println!("reset by watchdog: {}", iwdg::is_reset_cause(rcc));
+    iwdg::clear_reset_flags(rcc);
+
+    // KEY
+    Port::B.enable_clock(rcc);
+    set_pin_mode(Port::B, 1, PinMode::PullUpInput);
+    let key_pin = Gpiob::new(gpiob, 1);
+
+    // 启动看门狗，调试暂停时冻结计数
+    let mut watchdog = IndependentWatchdog::new(dp.IWDG);
+    watchdog.stop_on_debug(&dp.DBGMCU, true);
+    match watchdog.start(TIMEOUT_MS) {
+        Ok(timeout_us) => println!("watchdog started, timeout: {} us", timeout_us),
+        Err(err) => println!("watchdog error: {:?}", defmt::Debug2Format(&err)),
+    }
+
+    loop {
+        if key_pin.is_low() {
+            println!("key pressed, stop feeding...");
+            // 模拟程序卡死
+            loop {}
+        }
+
+        watchdog.feed();
+        delay_ms(&mut syst, FEED_INTERVAL_MS);
+    }
+}
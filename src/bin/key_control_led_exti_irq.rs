@@ -3,23 +3,27 @@
 #![no_main]
 #![allow(clippy::empty_loop)]
 
-use core::cell::RefCell;
-use core::mem::MaybeUninit;
-
+use stm32f1_core::hardware::exti::{Edge, ExtiPin};
+use stm32f1_core::hardware::gpio::{Gpioa, Gpiob};
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
 use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+use stm32f1_core::irq::IrqShared;
 
 use defmt::println;
 use defmt_rtt as _;
 use panic_probe as _;
 
-use cortex_m::interrupt::Mutex;
 use cortex_m::peripheral::NVIC;
 use cortex_m_rt::entry;
-use stm32f1::stm32f103::{interrupt, EXTI, GPIOA};
-use stm32f1::stm32f103::{CorePeripherals, Interrupt, Peripherals};
+use stm32f1::stm32f103::{interrupt, GPIOA, GPIOB};
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 定义 LED 连接的引脚号（PA1）
+const LED_PIN: u16 = 1;
+// 定义按键连接的引脚号（PB1）
+const KEY_PIN: u16 = 1;
 
-static G_KEY: Mutex<RefCell<Option<EXTI>>> = Mutex::new(RefCell::new(None));
-static mut G_LED: MaybeUninit<GPIOA> = MaybeUninit::uninit();
+static G_KEY: IrqShared<(GPIOA, GPIOB)> = IrqShared::new();
 
 #[entry]
 fn main() -> ! {
@@ -27,14 +31,15 @@ fn main() -> ! {
     let cp = CorePeripherals::take().unwrap();
 
     let gpioa = dp.GPIOA;
-    let gpiob = &dp.GPIOB;
+    let gpiob = dp.GPIOB;
     let rcc = &dp.RCC;
     let flash = &dp.FLASH;
     let mut syst = cp.SYST;
 
-    let exti = dp.EXTI;
+    let exti = &dp.EXTI;
     let afio = &dp.AFIO;
     let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
 
     // 设置 Flash
     set_flash(flash);
@@ -59,9 +64,6 @@ fn main() -> ! {
         .modify(|_, w| w.mode1().output50().cnf1().push_pull());
     gpioa.bsrr.write(|w| w.bs1().set_bit()); // 高电平
 
-    let led = unsafe { &mut *G_LED.as_mut_ptr() };
-    *led = gpioa;
-
     // KEY
     // 配置引脚为上拉输入模式
     gpiob
@@ -70,26 +72,26 @@ fn main() -> ! {
     // 设置引脚为高电平
     gpiob.bsrr.write(|w| w.bs1().set_bit());
 
+    let mut key = Gpiob::new(&gpiob, KEY_PIN);
     // 选择 EXTI 的触发源
-    // 这是因为 EXTI14 的触发源可以选择从 PA14 到 PG14 的任意一个引脚，而 AFIO 的 EXTICR4 寄存器的 EXTI14 位域用来配置这个选择。
-    // EXTI14 位域的值为 0 表示选择 PA14，为 1 表示选择 PB14，以此类推，直到 6 表示选择 PG14。
-    // 所以这里设置的是 1，就是选择了 PB14 引脚作为 EXTI14 的触发源。
-    afio.exticr1.modify(|_, w| unsafe { w.exti1().bits(0b01) });
-
+    // PB1 连接到 EXTI1，由 AFIO 的 EXTICR1 寄存器选择端口 B
+    key.make_interrupt_source(afio);
+    // 启用 EXTI 上升沿和下降沿生成中断
+    key.trigger_on_edge(exti, Edge::Both);
     // 从 EXTI 引脚启用外部中断
-    exti.imr.modify(|_, w| w.mr1().set_bit());
+    key.enable_interrupt(exti);
+    let irq = key.interrupt();
 
-    // 启用 EXTI 上升沿生成中断
-    exti.rtsr.modify(|_, w| w.tr1().enabled());
-    // 启用 EXTI 下升沿生成中断
-    exti.ftsr.modify(|_, w| w.tr1().enabled());
-
-    cortex_m::interrupt::free(|cs| G_KEY.borrow(cs).replace(Some(exti)));
+    // 将 LED 和按键移交给 EXTI1 中断
+    G_KEY.init(irq, (gpioa, gpiob));
 
     // 配置 NVIC 以使能 EXTI1 中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 抢占优先级 1，子优先级 0
+    set_priority(&mut nvic, irq, 1, 0).unwrap();
     unsafe {
-        NVIC::unmask(Interrupt::EXTI1);
-        nvic.set_priority(Interrupt::EXTI1, 0x1);
+        NVIC::unmask(irq);
     }
 
     println!("loop...");
@@ -100,28 +102,19 @@ fn main() -> ! {
 
 #[interrupt]
 fn EXTI1() {
-    cortex_m::interrupt::free(|cs| {
-        let mut binding = G_KEY.borrow(cs).borrow_mut();
-        let key = match binding.as_mut() {
-            Some(key) => key,
-            None => return,
-        };
+    G_KEY.with(|(gpioa, gpiob)| {
+        let mut key = Gpiob::new(gpiob, KEY_PIN);
 
         // 获取中断标识, 非中断标识退出
-        if !key.pr.read().pr1().is_pending() {
+        if !key.check_interrupt() {
             return;
         }
         println!("key...");
 
-        let led = unsafe { &mut *G_LED.as_mut_ptr() };
-
-        if led.idr.read().idr1().is_low() {
-            led.bsrr.write(|w| w.bs1().set_bit()); // 高电平
-        } else {
-            led.bsrr.write(|w| w.br1().set_bit()); // 低电平
-        }
+        // 翻转 LED 的状态
+        Gpioa::new(gpioa, LED_PIN).toggle();
 
         // 清除中断标志
-        key.pr.modify(|_, w| w.pr1().set_bit());
+        key.clear_interrupt_pending_bit();
     });
 }
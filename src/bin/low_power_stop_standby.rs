@@ -0,0 +1,119 @@
+//! 低功耗模式
+//! 启动时打印唤醒原因；之后进入停止模式，由 RTC 闹钟（每 5 秒）或按键（PB1）唤醒，
+//! 唤醒 5 次后进入待机模式，由 WKUP 引脚（PA0）上升沿唤醒并复位
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::exti::{self, Edge, ExtiPin};
+use stm32f1_core::hardware::gpio::Gpiob;
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
+use stm32f1_core::hardware::power::{self, Regulator, WakeMode};
+use stm32f1_core::hardware::rtc::{Rtc, RtcClockSource};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock};
+use stm32f1_core::irq::{IrqCounter, IrqShared};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{interrupt, CorePeripherals, Interrupt, Peripherals};
+
+// 定义按键连接的引脚号（PB1）
+const KEY_PIN: u16 = 1;
+// 闹钟间隔（秒）
+const ALARM_INTERVAL: u32 = 5;
+// 进入待机模式前唤醒的次数
+const STOP_CYCLES: u32 = 5;
+
+static G_RTC: IrqShared<Rtc> = IrqShared::new();
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let gpiob = &dp.GPIOB;
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let pwr = &dp.PWR;
+    let exti = &dp.EXTI;
+    let afio = &dp.AFIO;
+    let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    println!(
+        "wake reason: {:?}",
+        defmt::Debug2Format(&power::wake_reason(pwr))
+    );
+    power::clear_wake_flags(pwr);
+
+    // 使能 APB2 时钟
+    rcc.apb2enr
+        .modify(|_, w| w.iopben().enabled().afioen().enabled());
+
+    // KEY
+    // 配置引脚为上拉输入模式
+    gpiob
+        .crl
+        .modify(|_, w| w.mode1().input().cnf1().alt_push_pull());
+    gpiob.bsrr.write(|w| w.bs1().set_bit());
+    let mut key = Gpiob::new(gpiob, KEY_PIN);
+    key.make_interrupt_source(afio);
+    // 按键下降沿中断唤醒停止模式
+    power::enable_wakeup_line(exti, key.line(), Edge::Falling, WakeMode::Interrupt);
+
+    // RTC 闹钟经 EXTI 线 17 唤醒停止模式
+    let mut rtc = Rtc::new(dp.RTC, rcc, pwr, RtcClockSource::Lse);
+    rtc.set_alarm_after(ALARM_INTERVAL);
+    rtc.listen_alarm(exti);
+    G_RTC.init(Interrupt::RTCALARM, rtc);
+
+    // 配置 NVIC
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    set_priority(&mut nvic, Interrupt::RTCALARM, 1, 0).unwrap();
+    set_priority(&mut nvic, key.interrupt(), 1, 1).unwrap();
+    unsafe {
+        NVIC::unmask(Interrupt::RTCALARM);
+        NVIC::unmask(key.interrupt());
+    }
+
+    for cycle in 1..=STOP_CYCLES {
+        println!("stop {}...", cycle);
+        // 停止模式，唤醒后恢复 72MHz 时钟
+        power::stop(&mut scb, pwr, rcc, Regulator::LowPower);
+        println!("wake up, alarms: {}, keys: {}", ALARMS.get(), KEYS.get());
+    }
+
+    println!("standby...");
+    power::enable_wakeup_pin(pwr);
+    power::standby(&mut scb, pwr);
+}
+
+// 闹钟次数
+static ALARMS: IrqCounter = IrqCounter::new();
+// 按键次数
+static KEYS: IrqCounter = IrqCounter::new();
+
+#[interrupt]
+fn RTCALARM() {
+    G_RTC.with(|rtc| {
+        rtc.clear_alarm_flag();
+        ALARMS.increment();
+        rtc.set_alarm_after(ALARM_INTERVAL);
+    });
+}
+
+#[interrupt]
+fn EXTI1() {
+    exti::clear_pending(KEY_PIN as u8);
+    KEYS.increment();
+}
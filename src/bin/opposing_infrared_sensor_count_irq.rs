@@ -3,35 +3,40 @@
 #![no_main]
 #![allow(clippy::empty_loop)]
 
-use core::cell::RefCell;
-
+use stm32f1_core::hardware::exti::{Edge, ExtiPin};
+use stm32f1_core::hardware::gpio::Gpiob;
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
 use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+use stm32f1_core::irq::{IrqCounter, IrqShared};
 
 use defmt::println;
 use defmt_rtt as _;
 use panic_probe as _;
 
-use cortex_m::interrupt::Mutex;
 use cortex_m::peripheral::NVIC;
 use cortex_m_rt::entry;
-use stm32f1::stm32f103::{interrupt, EXTI};
-use stm32f1::stm32f103::{CorePeripherals, Interrupt, Peripherals};
+use stm32f1::stm32f103::{interrupt, GPIOB};
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 定义传感器连接的引脚号（PB14）
+const SENSOR_PIN: u16 = 14;
 
-static G_EXTI: Mutex<RefCell<Option<EXTI>>> = Mutex::new(RefCell::new(None));
+static G_SENSOR: IrqShared<GPIOB> = IrqShared::new();
 
 #[entry]
 fn main() -> ! {
     let dp = Peripherals::take().unwrap();
     let cp = CorePeripherals::take().unwrap();
 
-    let gpiob = &dp.GPIOB;
+    let gpiob = dp.GPIOB;
     let rcc = &dp.RCC;
     let flash = &dp.FLASH;
     let mut syst = cp.SYST;
 
-    let exti = dp.EXTI;
+    let exti = &dp.EXTI;
     let afio = &dp.AFIO;
     let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
 
     // 设置 Flash
     set_flash(flash);
@@ -51,34 +56,27 @@ fn main() -> ! {
     // 设置引脚为高电平
     gpiob.bsrr.write(|w| w.bs14().set_bit());
 
+    let mut sensor = Gpiob::new(&gpiob, SENSOR_PIN);
     // 选择 EXTI 的触发源
-    // 这是因为 EXTI14 的触发源可以选择从 PA14 到 PG14 的任意一个引脚，而 AFIO 的 EXTICR4 寄存器的 EXTI14 位域用来配置这个选择。
-    // EXTI14 位域的值为 0 表示选择 PA14，为 1 表示选择 PB14，以此类推，直到 6 表示选择 PG14。
-    // 所以这里设置的是 1，就是选择了 PB14 引脚作为 EXTI14 的触发源。
-    afio.exticr4.modify(|_, w| unsafe { w.exti14().bits(0b01) });
-    // afio.exticr4
-    //     .modify(|_, w| w.exti14().variant(1));
-
+    // EXTI14 的触发源可以选择从 PA14 到 PG14 的任意一个引脚，由 AFIO 的 EXTICR4 寄存器选择。
+    // 这里选择 PB14 引脚作为 EXTI14 的触发源。
+    sensor.make_interrupt_source(afio);
+    // 仅启用 EXTI 上升沿生成中断
+    sensor.trigger_on_edge(exti, Edge::Rising);
     // 从 EXTI 引脚启用外部中断
-    // exti.imr.modify(|_, w| w.mr14().masked().mr14().set_bit());
-    exti.imr.modify(|_, w| w.mr14().set_bit());
+    sensor.enable_interrupt(exti);
+    let irq = sensor.interrupt();
 
-    // Event mask register
-    // exti.emr.modify(|_, w| w.mr14().set_bit());
-
-    // 启用 EXTI 上升沿生成中断
-    exti.rtsr.modify(|_, w| w.tr14().enabled());
-    // 禁用 EXTI 下升沿生成中断
-    // exti.ftsr
-    // .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << 14)) });
-    exti.ftsr.modify(|_, w| w.tr14().disabled());
-
-    cortex_m::interrupt::free(|cs| G_EXTI.borrow(cs).replace(Some(exti)));
+    // 将传感器引脚移交给 EXTI15_10 中断
+    G_SENSOR.init(irq, gpiob);
 
     // 配置 NVIC 以使能 EXTI15_10 中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 抢占优先级 1，子优先级 0
+    set_priority(&mut nvic, irq, 1, 0).unwrap();
     unsafe {
-        NVIC::unmask(Interrupt::EXTI15_10);
-        nvic.set_priority(Interrupt::EXTI15_10, 0x1);
+        NVIC::unmask(irq);
     }
 
     println!("loop...");
@@ -90,32 +88,26 @@ fn main() -> ! {
 }
 
 // 计数器
-static mut COUNT: u32 = 0;
+static COUNT: IrqCounter = IrqCounter::new();
 
 #[interrupt]
 fn EXTI15_10() {
-    cortex_m::interrupt::free(|cs| {
-        let mut binding = G_EXTI.borrow(cs).borrow_mut();
-        let exti = match binding.as_mut() {
-            Some(exti) => exti,
-            None => return,
-        };
+    G_SENSOR.with(|gpiob| {
+        let mut sensor = Gpiob::new(gpiob, SENSOR_PIN);
 
         // 获取中断标识, 非中断标识退出
-        if !exti.pr.read().pr14().is_pending() {
+        if !sensor.check_interrupt() {
             return;
         }
 
-        unsafe {
-            COUNT += 1;
-        }
+        COUNT.increment();
 
         // 清除中断标志
-        exti.pr.modify(|_, w| w.pr14().set_bit());
+        sensor.clear_interrupt_pending_bit();
     });
 }
 
 /// 获取传感器计数
 fn get_sensor_count() -> u32 {
-    unsafe { COUNT }
+    COUNT.get()
 }
@@ -0,0 +1,87 @@
+//! PVD 电源电压监测
+//! VDD 跌落到 2.9V 以下或恢复时产生 PVD 中断，主程序打印电压跌落的次数和当前状态
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
+use stm32f1_core::hardware::pvd::{self, PvdLevel, SupplyEvent};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+use stm32f1_core::irq::{IrqCounter, IrqShared};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{interrupt, CorePeripherals, Interrupt, Peripherals, PWR};
+
+// 电压阈值
+const LEVEL: PvdLevel = PvdLevel::V2_9;
+
+static G_PWR: IrqShared<PWR> = IrqShared::new();
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let exti = &dp.EXTI;
+    let pwr = dp.PWR;
+    let mut syst = cp.SYST;
+    let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 使能 PVD，跌落和恢复都产生中断
+    pvd::enable(&pwr, LEVEL);
+    pvd::listen(exti, SupplyEvent::Both);
+    println!("PVD threshold: {} mV", LEVEL.millivolts());
+
+    // 将 PWR 移交给 PVD 中断
+    G_PWR.init(Interrupt::PVD, pwr);
+
+    // 配置 NVIC 以使能 PVD 中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 电源跌落需要尽快处理，使用最高抢占优先级
+    set_priority(&mut nvic, Interrupt::PVD, 0, 0).unwrap();
+    unsafe {
+        NVIC::unmask(Interrupt::PVD);
+    }
+
+    println!("loop...");
+    loop {
+        println!("drops: {}, recovers: {}", DROPS.get(), RECOVERS.get());
+        delay_ms(&mut syst, 1000);
+    }
+}
+
+// 电压跌落次数
+static DROPS: IrqCounter = IrqCounter::new();
+// 电压恢复次数
+static RECOVERS: IrqCounter = IrqCounter::new();
+
+#[interrupt]
+fn PVD() {
+    G_PWR.with(|pwr| {
+        pvd::clear_pending();
+
+        if pvd::is_below_threshold(pwr) {
+            // 在这里保存需要掉电保持的数据
+            DROPS.increment();
+            println!("supply drop!");
+        } else {
+            RECOVERS.increment();
+            println!("supply recovered");
+        }
+    });
+}
@@ -0,0 +1,53 @@
+//! PWM 呼吸灯
+//! TIM2 通道 1（PA0）输出 1kHz PWM，逐渐改变占空比实现呼吸效果
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::timer::{Channel, Timer};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use embedded_hal::pwm::SetDutyCycle;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // TIM2 通道 1 输出 PWM
+    let mut pwm = Timer::new(dp.TIM2, rcc).pwm(rcc, &[Channel::C1], 1_000);
+    println!("frequency: {:?}", pwm.get_frequency());
+
+    // 通过 embedded-hal 接口设置占空比
+    let mut led = pwm.channel(Channel::C1);
+
+    println!("loop...");
+    loop {
+        // 逐渐变亮
+        for percent in 0..=100 {
+            led.set_duty_cycle_percent(percent).unwrap();
+            delay_ms(&mut syst, 10);
+        }
+        // 逐渐变暗
+        for percent in (0..=100).rev() {
+            led.set_duty_cycle_percent(percent).unwrap();
+            delay_ms(&mut syst, 10);
+        }
+    }
+}
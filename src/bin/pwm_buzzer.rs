@@ -0,0 +1,52 @@
+//! PWM 蜂鸣器
+//! TIM3 通道 1（PA6）驱动无源蜂鸣器，改变 PWM 频率播放音阶
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::timer::{Channel, Timer};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 中音 Do ~ Si 的频率（Hz）
+const TONES: [u32; 7] = [523, 587, 659, 698, 784, 880, 988];
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // TIM3 通道 1 输出 PWM
+    let mut pwm = Timer::new(dp.TIM3, rcc).pwm(rcc, &[Channel::C1], TONES[0]);
+
+    println!("loop...");
+    loop {
+        for tone in TONES {
+            println!("tone: {:?}", tone);
+            pwm.set_frequency(tone);
+            // 修改频率后按新的最大值设置 50% 占空比
+            pwm.set_duty(Channel::C1, pwm.get_max_duty() / 2);
+            delay_ms(&mut syst, 500);
+        }
+
+        // 静音
+        pwm.set_duty(Channel::C1, 0);
+        delay_ms(&mut syst, 1000);
+    }
+}
@@ -0,0 +1,67 @@
+//! PWM 输入捕获
+//! TIM2 通道 1（PA0）输出 PWM，用杜邦线连接到 PA6，
+//! TIM3 以 PWM 输入模式测量频率和占空比
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::timer::capture::CaptureConfig;
+use stm32f1_core::hardware::timer::{Channel, Timer};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 输出频率（Hz）
+const FREQUENCY: u32 = 1000;
+// 计数频率 1MHz，分辨率 1us
+const TICK_HZ: u32 = 1_000_000;
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // TIM2 通道 1 输出 PWM
+    let mut pwm = Timer::new(dp.TIM2, rcc).pwm(rcc, &[Channel::C1], FREQUENCY);
+
+    // TIM3 通道 1（PA6）测量 PWM
+    let mut input =
+        Timer::new(dp.TIM3, rcc).pwm_input(rcc, Channel::C1, CaptureConfig::default(), TICK_HZ);
+
+    println!("loop...");
+    let mut percent = 10;
+    loop {
+        let duty = pwm.get_max_duty() as u32 * percent / 100;
+        pwm.set_duty(Channel::C1, duty as u16);
+        delay_ms(&mut syst, 10);
+
+        match input.wait() {
+            Ok(measurement) => println!(
+                "freq: {} Hz, period: {} us, high: {} us, duty: {}%",
+                measurement.frequency_hz(),
+                measurement.period_us(),
+                measurement.high_us(),
+                measurement.duty_percent()
+            ),
+            Err(err) => println!("error: {:?}", defmt::Debug2Format(&err)),
+        }
+
+        percent = if percent >= 90 { 10 } else { percent + 10 };
+        delay_ms(&mut syst, 500);
+    }
+}
@@ -0,0 +1,48 @@
+//! 旋转编码器
+//! TIM3 编码器接口，A 相接 PA6，B 相接 PA7，每秒打印位置、方向和转速
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::timer::qei::QeiConfig;
+use stm32f1_core::hardware::timer::Timer;
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 编码器模式 3，4 倍频计数
+    let mut qei = Timer::new(dp.TIM3, rcc).qei(rcc, QeiConfig::default());
+
+    println!("loop...");
+    loop {
+        let speed = qei.delta();
+        println!(
+            "position: {}, count: {}, direction: {}, speed: {}/s",
+            qei.position(),
+            qei.signed_count(),
+            defmt::Debug2Format(&qei.direction()),
+            speed
+        );
+        delay_ms(&mut syst, 1000);
+    }
+}
@@ -0,0 +1,105 @@
+//! RS-485 与单线半双工
+//! - USART2（TX PA2、RX PA3）接 MAX485，DE/RE 接 PA1，中断缓冲模式下自动控制驱动器，
+//!   每秒发送一次查询并打印收到的应答
+//! - USART3 单线半双工，PB10 外接上拉电阻作为单线总线，每秒发送一个查询字节并等待一个应答字节
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::gpio::Port;
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
+use stm32f1_core::hardware::serial::buffered::SerialBuffers;
+use stm32f1_core::hardware::serial::rs485::{DePolarity, DriverEnable};
+use stm32f1_core::hardware::serial::{Config, Error, Serial};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{interrupt, CorePeripherals, Interrupt, Peripherals, USART2};
+
+// RS-485 波特率
+const BAUDRATE: u32 = 9600;
+// 单线总线的查询字节
+const QUERY: u8 = 0x55;
+// 等待单线应答的轮询次数
+const REPLY_POLLS: u32 = 100_000;
+
+// 接收和发送缓冲区各 64 字节
+static BUFFERS: SerialBuffers<64, 64> = SerialBuffers::new();
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+    let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // RS-485：USART2 中断缓冲模式，DE 高电平有效
+    let config = Config {
+        baudrate: BAUDRATE,
+        ..Default::default()
+    };
+    let mut bus = Serial::new(dp.USART2, rcc, config)
+        .unwrap()
+        .into_buffered(&BUFFERS);
+    bus.set_driver_enable(rcc, DriverEnable::new(Port::A, 1, DePolarity::ActiveHigh));
+
+    // 单线半双工：USART3，PB10 复用开漏输出
+    let mut wire = Serial::new(dp.USART3, rcc, Config::default())
+        .unwrap()
+        .into_half_duplex();
+
+    // 配置 NVIC 以使能 USART2 中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 抢占优先级 1，子优先级 0
+    set_priority(&mut nvic, Interrupt::USART2, 1, 0).unwrap();
+    unsafe {
+        NVIC::unmask(Interrupt::USART2);
+    }
+
+    let mut buf = [0u8; 32];
+    loop {
+        // 发送查询，驱动器在最后一个字节发出后由中断释放
+        bus.write_all(b"PING\r\n");
+
+        // 单线总线：发送期间接收器关闭，发送完再等待应答
+        wire.write_all(&[QUERY]);
+        let reply = (0..REPLY_POLLS).find_map(|_| match wire.read() {
+            Err(Error::WouldBlock) => None,
+            result => Some(result),
+        });
+        match reply {
+            Some(Ok(byte)) => println!("wire reply: {:#x}", byte),
+            Some(Err(err)) => println!("wire error: {:?}", defmt::Debug2Format(&err)),
+            None => println!("wire timeout"),
+        }
+
+        delay_ms(&mut syst, 1000);
+
+        // 打印一秒内收到的 RS-485 应答
+        match bus.read_available(&mut buf) {
+            Ok(count) => println!("rs485 reply: {=[u8]}", &buf[..count]),
+            Err(err) => println!("rs485 error: {:?}", defmt::Debug2Format(&err)),
+        }
+    }
+}
+
+#[interrupt]
+fn USART2() {
+    BUFFERS.on_interrupt::<USART2>();
+}
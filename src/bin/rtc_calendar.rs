@@ -0,0 +1,68 @@
+//! RTC 日历
+//! RTC 计数器作为 Unix 时间戳，首次上电时设置日期，之后每秒打印日期时间和星期，
+//! 每天 08:00:00 闹钟
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::calendar::DateTime;
+use stm32f1_core::hardware::rtc::{Event, Rtc, RtcClockSource};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let pwr = &dp.PWR;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    let mut rtc = Rtc::new(dp.RTC, rcc, pwr, RtcClockSource::Lse);
+
+    // 计数值早于 2024 年说明备份域掉电，重新设置日期
+    let initial = DateTime::new(2024, 1, 1, 0, 0, 0).unwrap();
+    if rtc.datetime() < initial {
+        rtc.set_datetime(&initial).unwrap();
+    }
+
+    let alarm = rtc.set_alarm_daily(8, 0, 0).unwrap();
+    println!("next alarm: {}", alarm);
+
+    println!("loop...");
+    loop {
+        let now = rtc.datetime();
+        println!(
+            "{}-{}-{} {}:{}:{} weekday {}",
+            now.year,
+            now.month,
+            now.day,
+            now.hour,
+            now.minute,
+            now.second,
+            now.weekday().number()
+        );
+
+        if rtc.is_flag_set(Event::Alarm) {
+            println!("good morning!");
+            rtc.clear_alarm_flag();
+            rtc.set_alarm_daily(8, 0, 0);
+        }
+
+        delay_ms(&mut syst, 1000);
+    }
+}
@@ -0,0 +1,65 @@
+//! RTC 校准
+//! 在 PC13（TAMPER）输出 RTCCLK/64（LSE 时为 512Hz）的校准时钟，用频率计测量后
+//! 把测得的频率填入 `MEASURED_HZ`，计算 ppm 误差并写入 BKP_RTCCR 的 CAL
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::bkp::Backup;
+use stm32f1_core::hardware::rtc::{self, Rtc, RtcClockSource};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 校准输出的标称频率
+const NOMINAL_HZ: f32 = rtc::LSE_HZ as f32 / 64.0;
+// 频率计测得的校准输出频率
+const MEASURED_HZ: f32 = 512.02;
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let pwr = &dp.PWR;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    let rtc = Rtc::new(dp.RTC, rcc, pwr, RtcClockSource::Lse);
+    let mut backup = Backup::new(dp.BKP, rcc, pwr);
+
+    // PC13 输出校准时钟
+    backup.enable_calibration_output();
+
+    let ppm = rtc::frequency_error_ppm(MEASURED_HZ, NOMINAL_HZ);
+    match rtc::ppm_to_calibration(ppm) {
+        Ok(calibration) => {
+            backup.set_rtc_calibration(calibration);
+            println!(
+                "error: {} ppm, CAL: {}, corrected: {} ppm",
+                ppm,
+                calibration,
+                rtc::calibration_to_ppm(calibration)
+            );
+        }
+        Err(err) => println!("error: {} ppm, {:?}", ppm, defmt::Debug2Format(&err)),
+    }
+
+    println!("loop...");
+    loop {
+        println!("time: {}", rtc.current_time());
+        delay_ms(&mut syst, 1000);
+    }
+}
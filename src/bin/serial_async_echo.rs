@@ -0,0 +1,113 @@
+//! 异步串口回显
+//! USART1（TX PA9、RX PA10）115200 8N1，通过 `embedded_io_async` 接口异步读写，
+//! 回显任务与 LED 闪烁任务并发运行，由 USART1 中断和 SysTick 唤醒
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use core::future::Future;
+use core::pin::{pin, Pin};
+
+use stm32f1_core::executor::{self, timer::Timer};
+use stm32f1_core::hardware::gpio::{set_pin_mode, Gpioa, PinMode, Port};
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
+use stm32f1_core::hardware::serial::buffered::{BufferedSerial, SerialBuffers};
+use stm32f1_core::hardware::serial::{Config, Serial};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::{entry, exception};
+use embedded_io_async::{Read, Write};
+use stm32f1::stm32f103::{interrupt, CorePeripherals, Interrupt, Peripherals, USART1};
+
+// 定义 LED 连接的引脚号（PA1）
+const LED_PIN: u8 = 1;
+
+// 接收和发送缓冲区各 64 字节
+static BUFFERS: SerialBuffers<64, 64> = SerialBuffers::new();
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let gpioa = &dp.GPIOA;
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+    let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // LED
+    Port::A.enable_clock(rcc);
+    set_pin_mode(Port::A, LED_PIN, PinMode::PushPullOutput);
+    let led = Gpioa::new(gpioa, LED_PIN as u16);
+
+    // 配置 USART1 为中断驱动的缓冲模式
+    let serial = Serial::new(dp.USART1, rcc, Config::default())
+        .unwrap()
+        .into_buffered(&BUFFERS);
+
+    // 配置 NVIC 以使能 USART1 中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 抢占优先级 1，子优先级 0
+    set_priority(&mut nvic, Interrupt::USART1, 1, 0).unwrap();
+    unsafe {
+        NVIC::unmask(Interrupt::USART1);
+    }
+
+    // 1ms 节拍
+    executor::timer::start(&mut syst, 1000).unwrap();
+
+    let blink = pin!(blink_task(led));
+    let echo = pin!(echo_task(serial));
+    let mut tasks: [Pin<&mut dyn Future<Output = ()>>; 2] = [blink, echo];
+
+    println!("run...");
+    executor::run(&mut tasks);
+}
+
+/// LED 每 500ms 翻转一次
+async fn blink_task(led: Gpioa<'_>) {
+    loop {
+        led.toggle();
+        Timer::after_ms(500).await;
+    }
+}
+
+/// 把收到的数据原样发回
+async fn echo_task(mut serial: BufferedSerial<USART1, 64, 64>) {
+    // BufferedSerial 的同名阻塞方法优先，这里显式调用异步接口
+    Write::write_all(&mut serial, b"async echo\r\n")
+        .await
+        .unwrap();
+
+    let mut buf = [0; 16];
+    loop {
+        match Read::read(&mut serial, &mut buf).await {
+            Ok(count) => Write::write_all(&mut serial, &buf[..count]).await.unwrap(),
+            Err(err) => println!("serial error: {:?}", defmt::Debug2Format(&err)),
+        }
+    }
+}
+
+#[exception]
+fn SysTick() {
+    executor::timer::on_tick();
+}
+
+#[interrupt]
+fn USART1() {
+    BUFFERS.on_interrupt::<USART1>();
+}
@@ -0,0 +1,111 @@
+//! 串口 DMA 接收不定长数据帧
+//! USART1（TX PA9、RX PA10）921600 8N1，DMA 循环接收到 256 字节的缓冲区，
+//! 总线空闲中断中取出一帧并统计帧数、字节数和校验和；主程序每秒通过 DMA 发送一行状态
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
+use stm32f1_core::hardware::serial::dma::DmaRx;
+use stm32f1_core::hardware::serial::{Config, Serial};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+use stm32f1_core::irq::{IrqCounter, IrqShared};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{interrupt, CorePeripherals, Interrupt, Peripherals, USART1};
+
+// 波特率
+const BAUDRATE: u32 = 921_600;
+
+// DMA 接收缓冲区，交给 DmaRx 独占
+static mut RX_BUFFER: [u8; 256] = [0; 256];
+
+static G_RX: IrqShared<DmaRx<USART1>> = IrqShared::new();
+
+// 帧数、字节数和最后一帧的校验和
+static FRAMES: IrqCounter = IrqCounter::new();
+static BYTES: IrqCounter = IrqCounter::new();
+static CHECKSUM: IrqCounter = IrqCounter::new();
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+    let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 配置 USART1，转换为 DMA 收发
+    let config = Config {
+        baudrate: BAUDRATE,
+        ..Default::default()
+    };
+    let serial = Serial::new(dp.USART1, rcc, config).unwrap();
+    println!("baudrate: {}", serial.baudrate());
+    let rx_buffer = unsafe { &mut *core::ptr::addr_of_mut!(RX_BUFFER) };
+    let (mut tx, mut rx) = serial.split_dma(rcc, rx_buffer);
+
+    // 缓冲区过半或写满时也取出数据，防止连续数据流覆盖未处理的数据
+    rx.listen_dma();
+    // 将接收端移交给 USART1 中断
+    G_RX.init(Interrupt::USART1, rx);
+
+    // 配置 NVIC 以使能 USART1 和 DMA1 通道 5 中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 抢占优先级 1，子优先级 0
+    set_priority(&mut nvic, Interrupt::USART1, 1, 0).unwrap();
+    // DMA 中断转交给 USART1 中断处理，同一抢占优先级不会互相抢占
+    set_priority(&mut nvic, Interrupt::DMA1_CHANNEL5, 1, 1).unwrap();
+    unsafe {
+        NVIC::unmask(Interrupt::USART1);
+        NVIC::unmask(Interrupt::DMA1_CHANNEL5);
+    }
+
+    loop {
+        // 发送期间缓冲区归 DMA 所有，完成后归还
+        let transfer = tx.write("status: ok\r\n");
+        (tx, _) = transfer.wait();
+
+        println!(
+            "frames: {}, bytes: {}, checksum: {:#x}",
+            FRAMES.get(),
+            BYTES.get(),
+            CHECKSUM.get()
+        );
+        delay_ms(&mut syst, 1000);
+    }
+}
+
+#[interrupt]
+fn USART1() {
+    G_RX.with(|rx| {
+        // 帧数据直接借用 DMA 缓冲区，必须在本次中断中处理完
+        if let Some(frame) = rx.read_frame() {
+            let checksum = frame.iter().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+            FRAMES.increment();
+            BYTES.add(frame.len() as u32);
+            CHECKSUM.set(checksum as u32);
+        }
+    });
+}
+
+#[interrupt]
+fn DMA1_CHANNEL5() {
+    // 接收端归 USART1 中断所有，挂起 USART1 中断后在其中读取
+    NVIC::pend(Interrupt::USART1);
+}
@@ -0,0 +1,79 @@
+//! 串口回显
+//! USART1（TX PA9、RX PA10）115200 8N1，先以阻塞方式打印欢迎信息，
+//! 再切换为中断驱动的缓冲模式，把收到的数据原样发回，收到回车时打印已接收的字节数
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use core::fmt::Write;
+
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
+use stm32f1_core::hardware::serial::buffered::SerialBuffers;
+use stm32f1_core::hardware::serial::{Config, Error, Serial};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{interrupt, CorePeripherals, Interrupt, Peripherals, USART1};
+
+// 接收和发送缓冲区各 64 字节
+static BUFFERS: SerialBuffers<64, 64> = SerialBuffers::new();
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 配置 USART1，阻塞方式发送
+    let mut serial = Serial::new(dp.USART1, rcc, Config::default()).unwrap();
+    println!("baudrate: {}", serial.baudrate());
+    writeln!(serial, "hello from USART1\r").unwrap();
+
+    // 切换为中断驱动的缓冲模式
+    let mut serial = serial.into_buffered(&BUFFERS);
+
+    // 配置 NVIC 以使能 USART1 中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 抢占优先级 1，子优先级 0
+    set_priority(&mut nvic, Interrupt::USART1, 1, 0).unwrap();
+    unsafe {
+        NVIC::unmask(Interrupt::USART1);
+    }
+
+    let mut received: u32 = 0;
+    loop {
+        match serial.read() {
+            Ok(byte) => {
+                received += 1;
+                // 回显
+                serial.write_all(&[byte]);
+                if byte == b'\r' {
+                    write!(serial, "\nreceived: {}\r\n", received).unwrap();
+                }
+            }
+            Err(Error::WouldBlock) => {}
+            Err(err) => println!("serial error: {:?}", defmt::Debug2Format(&err)),
+        }
+    }
+}
+
+#[interrupt]
+fn USART1() {
+    BUFFERS.on_interrupt::<USART1>();
+}
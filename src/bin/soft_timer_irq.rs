@@ -0,0 +1,99 @@
+//! 软件定时器
+//! 使用 SysTick 1ms 节拍同时驱动 LED 闪烁、周期轮询和单次超时三个软件定时器
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use core::cell::RefCell;
+
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::start_tick};
+use stm32f1_core::soft_timer::{Mode, SoftTimers};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::interrupt::Mutex;
+use cortex_m_rt::{entry, exception};
+use stm32f1::stm32f103::{CorePeripherals, Peripherals, GPIOA};
+
+static G_TIMERS: Mutex<RefCell<SoftTimers<4>>> = Mutex::new(RefCell::new(SoftTimers::new()));
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let gpioa = &dp.GPIOA;
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 启用 APB2 GPIOA 的时钟
+    rcc.apb2enr.modify(|_, w| w.iopaen().enabled());
+
+    // LED
+    // 配置引脚为推挽输出模式
+    gpioa
+        .crl
+        .modify(|_, w| w.mode1().output50().cnf1().push_pull());
+
+    // 注册定时器
+    let (blink, poll, timeout) = cortex_m::interrupt::free(|cs| {
+        let mut timers = G_TIMERS.borrow(cs).borrow_mut();
+        // LED 每 500ms 翻转一次
+        let blink = timers.start(Mode::Periodic, 500, Some(toggle_led)).unwrap();
+        // 每 1s 轮询一次传感器
+        let poll = timers.start_periodic(1000, None).unwrap();
+        // 5s 后超时
+        let timeout = timers.start_one_shot(5000, None).unwrap();
+        (blink, poll, timeout)
+    });
+
+    // 1ms 节拍
+    start_tick(&mut syst, 1000).unwrap();
+
+    println!("loop...");
+    loop {
+        cortex_m::interrupt::free(|cs| {
+            let mut timers = G_TIMERS.borrow(cs).borrow_mut();
+
+            if timers.take_expired(poll) {
+                println!("poll: {:?}", timers.now());
+            }
+
+            if timers.take_expired(timeout) {
+                println!("timeout, blink faster and stop polling");
+                timers.reschedule(blink, 100).unwrap();
+                timers.cancel(poll).unwrap();
+            }
+        });
+
+        // 等待下一个中断
+        cortex_m::asm::wfi();
+    }
+}
+
+#[exception]
+fn SysTick() {
+    // 释放借用后再执行到期的回调
+    let expired = cortex_m::interrupt::free(|cs| G_TIMERS.borrow(cs).borrow_mut().tick());
+    expired.run();
+}
+
+/// LED 闪烁回调，在 SysTick 中断中执行
+fn toggle_led() {
+    // 回调没有上下文参数，这里直接通过寄存器地址访问 GPIOA
+    let gpioa = unsafe { &*GPIOA::ptr() };
+    if gpioa.odr.read().odr1().is_low() {
+        gpioa.bsrr.write(|w| w.bs1().set_bit()); // 高电平
+    } else {
+        gpioa.bsrr.write(|w| w.br1().set_bit()); // 低电平
+    }
+}
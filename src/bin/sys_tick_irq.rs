@@ -5,6 +5,7 @@
 
 use cortex_m::peripheral::syst::SystClkSource;
 use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock};
+use stm32f1_core::irq::IrqCounter;
 
 use defmt::println;
 use defmt_rtt as _;
@@ -52,16 +53,14 @@ fn main() -> ! {
 }
 
 // 计数器
-static mut COUNT: u32 = 0;
+static COUNT: IrqCounter = IrqCounter::new();
 
 #[exception]
 fn SysTick() {
-    unsafe {
-        COUNT += 1;
-    }
+    COUNT.increment();
 }
 
 /// 获取计数
 fn get_count() -> u32 {
-    unsafe { COUNT }
+    COUNT.get()
 }
@@ -0,0 +1,65 @@
+//! TIM1 互补 PWM
+//! 通道 1 在 PA8（CH1）和 PB13（CH1N）输出 20kHz 互补 PWM，死区 500ns，
+//! PB12（BKIN）拉低时刹车，两路输出立即关闭
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::timer::advanced::BreakConfig;
+use stm32f1_core::hardware::timer::{Channel, Timer};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// PWM 频率（Hz）
+const FREQUENCY: u32 = 20_000;
+// 死区时间（ns）
+const DEAD_TIME_NS: u32 = 500;
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    let mut pwm = Timer::new(dp.TIM1, rcc).pwm(rcc, &[Channel::C1], FREQUENCY);
+    // 先关闭主输出，配置完成后再使能
+    pwm.disable_main_output();
+    pwm.enable_complementary(rcc, Channel::C1);
+    let dtg = pwm.set_dead_time_ns(DEAD_TIME_NS).unwrap();
+    println!("dtg: {:#x}, dead time: {} ns", dtg, pwm.dead_time_ns());
+
+    // 刹车撤销后不自动恢复，需要软件确认后重新使能主输出
+    pwm.enable_break(rcc, BreakConfig::default());
+    pwm.set_duty(Channel::C1, pwm.get_max_duty() / 2);
+    pwm.enable_main_output();
+
+    println!("loop...");
+    loop {
+        if pwm.is_break() {
+            println!("break!");
+            pwm.clear_break();
+            // 等待刹车输入撤销后恢复输出
+            delay_ms(&mut syst, 1000);
+            if !pwm.is_break() {
+                pwm.enable_main_output();
+                println!("resume");
+            }
+        }
+        delay_ms(&mut syst, 100);
+    }
+}
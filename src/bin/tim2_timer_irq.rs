@@ -3,19 +3,19 @@
 #![no_main]
 #![allow(clippy::empty_loop)]
 
-use core::cell::RefCell;
-
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
 use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock};
+use stm32f1_core::irq::{IrqCounter, IrqShared};
 
 use defmt::println;
 use defmt_rtt as _;
 use panic_probe as _;
 
-use cortex_m::{interrupt::Mutex, peripheral::NVIC};
+use cortex_m::peripheral::NVIC;
 use cortex_m_rt::entry;
 use stm32f1::stm32f103::{interrupt, CorePeripherals, Interrupt, Peripherals, TIM2};
 
-static G_TIM2: Mutex<RefCell<Option<TIM2>>> = Mutex::new(RefCell::new(None));
+static G_TIM2: IrqShared<TIM2> = IrqShared::new();
 
 #[entry]
 fn main() -> ! {
@@ -26,6 +26,7 @@ fn main() -> ! {
     let flash = &dp.FLASH;
     let tim2 = dp.TIM2;
     let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
 
     // 设置 Flash
     set_flash(flash);
@@ -54,12 +55,16 @@ fn main() -> ! {
     // 使能 TIM2
     tim2.cr1.modify(|_, w| w.cen().enabled());
 
-    cortex_m::interrupt::free(|cs| G_TIM2.borrow(cs).replace(Some(tim2)));
+    // 将 TIM2 移交给 TIM2 中断
+    G_TIM2.init(Interrupt::TIM2, tim2);
 
     // 配置 NVIC 以使能中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 抢占优先级 1，子优先级 0
+    set_priority(&mut nvic, Interrupt::TIM2, 1, 0).unwrap();
     unsafe {
         NVIC::unmask(Interrupt::TIM2);
-        nvic.set_priority(Interrupt::TIM2, 0x1);
     }
 
     println!("loop...");
@@ -70,25 +75,17 @@ fn main() -> ! {
 }
 
 // 计数器
-static mut COUNT: u32 = 0;
+static COUNT: IrqCounter = IrqCounter::new();
 
 #[interrupt]
 fn TIM2() {
-    cortex_m::interrupt::free(|cs| {
-        let mut binding = G_TIM2.borrow(cs).borrow_mut();
-        let tim2 = match binding.as_mut() {
-            Some(tim2) => tim2,
-            None => return,
-        };
-
+    G_TIM2.with(|tim2| {
         // 获取中断标识, 非中断标识退出
         if !tim2.sr.read().uif().bit_is_set() {
             return;
         }
 
-        unsafe {
-            COUNT += 1;
-        }
+        COUNT.increment();
 
         // 清除中断标志
         tim2.sr.modify(|_, w| w.uif().clear_bit());
@@ -97,5 +94,5 @@ fn TIM2() {
 
 /// 获取计数
 fn get_count() -> u32 {
-    unsafe { COUNT }
+    COUNT.get()
 }
@@ -0,0 +1,78 @@
+//! 定时器级联
+//! TIM2 与 TIM3 级联为 1MHz 的 32 位计数器，测量按键（PB1）两次按下的间隔；
+//! TIM4 通道 1（PB6）与 TIM1 通道 1（PA8）同步启动，输出相位对齐的 PWM
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::gpio::Gpiob;
+use stm32f1_core::hardware::timer::{Channel, Timer};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 定义按键连接的引脚号（PB1）
+const KEY_PIN: u16 = 1;
+// 计数频率 1MHz
+const TICK_HZ: u32 = 1_000_000;
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let gpiob = &dp.GPIOB;
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 使能 APB2 时钟
+    rcc.apb2enr.modify(|_, w| w.iopben().enabled());
+
+    // KEY
+    // 配置引脚为上拉输入模式
+    gpiob
+        .crl
+        .modify(|_, w| w.mode1().input().cnf1().alt_push_pull());
+    gpiob.bsrr.write(|w| w.bs1().set_bit());
+    let key = Gpiob::new(gpiob, KEY_PIN);
+
+    // 32 位计数器：TIM2 为低 16 位，TIM3 为高 16 位
+    let counter = Timer::new(dp.TIM2, rcc)
+        .chain(Timer::new(dp.TIM3, rcc), TICK_HZ)
+        .unwrap();
+
+    // TIM4 为主定时器，TIM1 为从定时器（ITR3）
+    let mut master = Timer::new(dp.TIM4, rcc).pwm(rcc, &[Channel::C1], 1000);
+    let mut slave = Timer::new(dp.TIM1, rcc).pwm(rcc, &[Channel::C1], 1000);
+    master.set_duty(Channel::C1, master.get_max_duty() / 2);
+    slave.set_duty(Channel::C1, slave.get_max_duty() / 4);
+    master.synchronize(&mut slave).unwrap();
+    master.start_synchronized();
+
+    println!("loop...");
+    let mut last = counter.count();
+    loop {
+        if key.is_low() {
+            let now = counter.count();
+            let elapsed = now.wrapping_sub(last);
+            last = now;
+            println!("interval: {} ms", elapsed / 1000);
+
+            // 消抖并等待释放
+            delay_ms(&mut syst, 20);
+            while key.is_low() {}
+        }
+    }
+}
@@ -5,10 +5,9 @@ use defmt_rtt as _;
 use panic_probe as _;
 
 use cortex_m_rt::entry;
-use embedded_hal::digital::{
-    ErrorKind, ErrorType, OutputPin, PinState, StatefulOutputPin, ToggleableOutputPin,
-};
+use embedded_hal::digital::{ErrorKind, ErrorType, OutputPin, PinState, StatefulOutputPin};
 use stm32f1::stm32f103::{Peripherals, GPIOC};
+use stm32f1_core::hardware::delay::delay_ms;
 
 // 定义 LED 连接的引脚号（PC13）
 const LED_PIN: u16 = 13;
@@ -30,12 +29,12 @@ fn main() -> ! {
     loop {
         // 翻转 LED 的状态
         led.toggle().unwrap();
-        // 延时大约一秒钟
-        delay(8_000_000);
+        // 延时一秒钟（未配置时钟树，按 HSI 8MHz 计算）
+        delay_ms(1000);
     }
 }
 
-// 定义一个简单的输出引脚结构体，实现 OutputPin 和 ToggleableOutputPin 特征
+// 定义一个简单的输出引脚结构体，实现 OutputPin 和 StatefulOutputPin 特征
 struct Pin<'a> {
     gpio: &'a GPIOC,
     pin: u16,
@@ -50,15 +49,6 @@ impl<'a> Pin<'a> {
 impl<'a> ErrorType for Pin<'a> {
     type Error = ErrorKind;
 }
-impl<'a> StatefulOutputPin for &mut Pin<'a> {
-    fn is_set_high(&self) -> Result<bool, Self::Error> {
-        todo!()
-    }
-
-    fn is_set_low(&self) -> Result<bool, Self::Error> {
-        todo!()
-    }
-}
 
 impl<'a> OutputPin for Pin<'a> {
     fn set_low(&mut self) -> Result<(), Self::Error> {
@@ -82,7 +72,15 @@ impl<'a> OutputPin for Pin<'a> {
     }
 }
 
-impl<'a> ToggleableOutputPin for Pin<'a> {
+impl<'a> StatefulOutputPin for Pin<'a> {
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.gpio.odr.read().bits() & (1 << self.pin) != 0)
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_set_high()?)
+    }
+
     fn toggle(&mut self) -> Result<(), Self::Error> {
         if self.is_set_low().unwrap() {
             self.set_high()
@@ -91,10 +89,3 @@ impl<'a> ToggleableOutputPin for Pin<'a> {
         }
     }
 }
-
-// 定义一个简单的延时函数，使用忙等待的方式
-fn delay(cycles: u32) {
-    for _ in 0..cycles {
-        cortex_m::asm::nop();
-    }
-}
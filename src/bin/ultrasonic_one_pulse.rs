@@ -0,0 +1,69 @@
+//! 超声波测距
+//! TIM3 单脉冲模式在 PA6 输出 10us 的触发脉冲（Trig），
+//! TIM2 PWM 输入模式在 PA0 测量回响信号（Echo）的高电平时间
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use stm32f1_core::hardware::timer::capture::{CaptureConfig, Error};
+use stm32f1_core::hardware::timer::opm::OnePulseConfig;
+use stm32f1_core::hardware::timer::{Channel, Timer};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{CorePeripherals, Peripherals};
+
+// 计数频率 1MHz，分辨率 1us
+const TICK_HZ: u32 = 1_000_000;
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 触发脉冲：延时 1us（至少 1 个计数），脉宽 10us
+    let mut trig = Timer::new(dp.TIM3, rcc).one_pulse(rcc, Channel::C1, OnePulseConfig::default());
+    trig.set_pulse_us(1, 10).unwrap();
+
+    // 回响信号
+    let mut echo =
+        Timer::new(dp.TIM2, rcc).pwm_input(rcc, Channel::C1, CaptureConfig::default(), TICK_HZ);
+
+    println!("loop...");
+    loop {
+        // 丢弃上一次测量残留的数据
+        let _ = echo.read_pulse();
+
+        trig.trigger();
+        trig.wait();
+
+        // 最远 4m 时回响约 23ms
+        delay_ms(&mut syst, 30);
+
+        match echo.read_pulse() {
+            Ok(ticks) => {
+                let us = echo.ticks_to_us(ticks);
+                // 声速 340m/s，往返距离，1cm 约 58us
+                println!("distance: {} cm", us / 58);
+            }
+            Err(Error::WouldBlock) => println!("no echo"),
+            Err(err) => println!("error: {:?}", defmt::Debug2Format(&err)),
+        }
+
+        delay_ms(&mut syst, 200);
+    }
+}
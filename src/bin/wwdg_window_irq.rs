@@ -0,0 +1,123 @@
+//! 窗口看门狗
+//! 看门狗超时 50ms、窗口 20ms，主循环每 30ms 刷新一次；
+//! 按住按键（PB1）时提前刷新被拒绝，之后停止刷新，提前唤醒中断中打印最后的日志后系统复位
+#![no_std]
+#![no_main]
+#![allow(clippy::empty_loop)]
+
+use core::cell::RefCell;
+
+use stm32f1_core::hardware::cfgr::PCLK1_HZ;
+use stm32f1_core::hardware::gpio::{set_pin_mode, Gpiob, PinMode, Port};
+use stm32f1_core::hardware::iwdg::clear_reset_flags;
+use stm32f1_core::hardware::nvic::{set_priority, set_priority_grouping, PriorityGrouping};
+use stm32f1_core::hardware::wwdg::{self, WindowWatchdog};
+use stm32f1_core::hardware::{acr::set_flash, cfgr::set_clock, syst::delay_ms};
+
+use defmt::println;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use cortex_m::interrupt::Mutex;
+use cortex_m::peripheral::NVIC;
+use cortex_m_rt::entry;
+use stm32f1::stm32f103::{interrupt, CorePeripherals, Interrupt, Peripherals};
+
+// 看门狗超时时间（微秒）
+const TIMEOUT_US: u32 = 50_000;
+// 窗口时间（微秒），刷新后这段时间内不允许再刷新
+const WINDOW_US: u32 = 20_000;
+// 刷新间隔（毫秒），位于窗口时间和超时时间之间
+const REFRESH_INTERVAL_MS: u32 = 30;
+
+// 主程序刷新，中断中清除提前唤醒标志
+static G_WWDG: Mutex<RefCell<Option<WindowWatchdog>>> = Mutex::new(RefCell::new(None));
+
+#[entry]
+fn main() -> ! {
+    let dp = Peripherals::take().unwrap();
+    let cp = CorePeripherals::take().unwrap();
+
+    let gpiob = &dp.GPIOB;
+    let rcc = &dp.RCC;
+    let flash = &dp.FLASH;
+    let mut syst = cp.SYST;
+    let mut nvic = cp.NVIC;
+    let mut scb = cp.SCB;
+
+    // 设置 Flash
+    set_flash(flash);
+
+    // 设置时钟
+    set_clock(rcc);
+
+    // 检查复位原因
+    println!("reset by window watchdog: {}", wwdg::is_reset_cause(rcc));
+    clear_reset_flags(rcc);
+
+    // KEY
+    Port::B.enable_clock(rcc);
+    set_pin_mode(Port::B, 1, PinMode::PullUpInput);
+    let key_pin = Gpiob::new(gpiob, 1);
+
+    // 配置 NVIC 以使能 WWDG 中断
+    // 2 位抢占优先级，2 位子优先级
+    set_priority_grouping(&mut scb, PriorityGrouping::Group2);
+    // 提前唤醒后只剩一个计数周期，使用最高抢占优先级
+    set_priority(&mut nvic, Interrupt::WWDG, 0, 0).unwrap();
+
+    // 启动看门狗，调试暂停时冻结计数
+    let mut watchdog = WindowWatchdog::new(dp.WWDG, rcc);
+    watchdog.stop_on_debug(&dp.DBGMCU, true);
+    let config = watchdog.start(TIMEOUT_US, WINDOW_US).unwrap();
+    println!(
+        "watchdog started, timeout: {} us, window: {} us",
+        config.timeout_us(PCLK1_HZ),
+        config.window_us(PCLK1_HZ)
+    );
+    watchdog.clear_early_wakeup();
+    watchdog.listen_early_wakeup();
+
+    cortex_m::interrupt::free(|cs| G_WWDG.borrow(cs).replace(Some(watchdog)));
+    unsafe {
+        NVIC::unmask(Interrupt::WWDG);
+    }
+
+    loop {
+        delay_ms(&mut syst, REFRESH_INTERVAL_MS);
+        refresh();
+
+        if key_pin.is_low() {
+            // 刚刚刷新过，还未进入窗口，刷新会被拒绝
+            println!("key pressed, refresh too early...");
+            refresh();
+            // 模拟程序卡死
+            loop {}
+        }
+    }
+}
+
+/// 刷新看门狗
+fn refresh() {
+    let result = cortex_m::interrupt::free(|cs| {
+        G_WWDG
+            .borrow(cs)
+            .borrow_mut()
+            .as_mut()
+            .map(|watchdog| watchdog.refresh())
+    });
+    if let Some(Err(err)) = result {
+        println!("refresh error: {:?}", defmt::Debug2Format(&err));
+    }
+}
+
+#[interrupt]
+fn WWDG() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(watchdog) = G_WWDG.borrow(cs).borrow_mut().as_mut() {
+            watchdog.clear_early_wakeup();
+            // 不再刷新，记录最后的日志后等待复位
+            println!("watchdog early wakeup, counter: {:#x}", watchdog.counter());
+        }
+    });
+}
@@ -0,0 +1,458 @@
+//! 日历
+//!
+//! RTC 计数器只是一个 32 位的秒数，这里把它解释为 Unix 时间戳
+//! （1970-01-01 00:00:00 起的秒数），与年月日、时分秒和星期互相转换。
+//! 32 位无符号秒数可以表示到 2106-02-07 06:28:15。
+//!
+//! ```rust
+//! let datetime = DateTime::new(2024, 2, 29, 12, 0, 0)?;
+//! rtc.set_datetime(&datetime)?;
+//!
+//! let now = rtc.datetime();
+//! println!("{}-{}-{} {:?}", now.year, now.month, now.day, now.weekday());
+//!
+//! // 每天 07:30:00 的闹钟
+//! rtc.set_alarm_daily(7, 30, 0);
+//! ```
+
+/// 每天的秒数
+pub const SECONDS_PER_DAY: u32 = 86_400;
+/// 可以表示的最早年份
+pub const MIN_YEAR: u16 = 1970;
+/// 可以表示的最晚年份
+pub const MAX_YEAR: u16 = 2106;
+
+/// 日历错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 年月日不合法
+    InvalidDate,
+    /// 时分秒不合法
+    InvalidTime,
+    /// 超出 32 位时间戳的范围
+    OutOfRange,
+}
+
+/// 星期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// 由 ISO 序号得到星期，星期一为 1，星期日为 7
+    pub const fn from_number(number: u8) -> Option<Self> {
+        match number {
+            1 => Some(Weekday::Monday),
+            2 => Some(Weekday::Tuesday),
+            3 => Some(Weekday::Wednesday),
+            4 => Some(Weekday::Thursday),
+            5 => Some(Weekday::Friday),
+            6 => Some(Weekday::Saturday),
+            7 => Some(Weekday::Sunday),
+            _ => None,
+        }
+    }
+
+    /// ISO 序号，星期一为 1，星期日为 7
+    pub const fn number(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// 距离星期一的天数
+    const fn days_from_monday(self) -> u32 {
+        self as u32
+    }
+}
+
+/// 是否为闰年
+pub const fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+/// 某月的天数
+pub const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// 日期时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    /// 年，1970~2106
+    pub year: u16,
+    /// 月，1~12
+    pub month: u8,
+    /// 日，1~31
+    pub day: u8,
+    /// 时，0~23
+    pub hour: u8,
+    /// 分，0~59
+    pub minute: u8,
+    /// 秒，0~59
+    pub second: u8,
+}
+
+impl DateTime {
+    /// 创建日期时间，检查各字段是否合法以及是否在时间戳范围内
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, Error> {
+        let datetime = DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        };
+        datetime.timestamp()?;
+        Ok(datetime)
+    }
+
+    /// 由时间戳得到日期时间
+    pub fn from_timestamp(timestamp: u32) -> Self {
+        let days = timestamp / SECONDS_PER_DAY;
+        let seconds = timestamp % SECONDS_PER_DAY;
+        let (year, month, day) = civil_from_days(days);
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+
+    /// 转换为时间戳
+    pub fn timestamp(&self) -> Result<u32, Error> {
+        if self.year < MIN_YEAR
+            || self.year > MAX_YEAR
+            || self.month == 0
+            || self.day == 0
+            || self.day > days_in_month(self.year, self.month)
+        {
+            return Err(if (MIN_YEAR..=MAX_YEAR).contains(&self.year) {
+                Error::InvalidDate
+            } else {
+                Error::OutOfRange
+            });
+        }
+        if self.hour > 23 || self.minute > 59 || self.second > 59 {
+            return Err(Error::InvalidTime);
+        }
+
+        // 年份已检查不早于 1970，天数不为负
+        let days = days_from_civil(self.year, self.month, self.day) as u64;
+        let seconds = days * SECONDS_PER_DAY as u64 + self.seconds_of_day() as u64;
+        u32::try_from(seconds).map_err(|_| Error::OutOfRange)
+    }
+
+    /// 星期
+    /// 年份不在时间戳范围内时按公历推算，不会溢出
+    pub fn weekday(&self) -> Weekday {
+        weekday_from_days(days_from_civil(self.year, self.month, self.day))
+    }
+
+    /// 当天已经过去的秒数
+    pub fn seconds_of_day(&self) -> u32 {
+        self.hour as u32 * 3600 + self.minute as u32 * 60 + self.second as u32
+    }
+
+    /// 当年的第几天，1 月 1 日为 1
+    pub fn day_of_year(&self) -> u16 {
+        let days: u16 = (1..self.month)
+            .map(|month| days_in_month(self.year, month) as u16)
+            .sum();
+        days + self.day as u16
+    }
+}
+
+/// 时间戳对应的星期
+pub fn weekday(timestamp: u32) -> Weekday {
+    weekday_from_days((timestamp / SECONDS_PER_DAY).into())
+}
+
+/// `now` 之后（不含 `now`）下一个每天 `hour:minute:second` 的时间戳
+/// 超出时间戳范围时返回 `None`
+pub fn next_daily(now: u32, hour: u8, minute: u8, second: u8) -> Option<u32> {
+    let time = time_of_day(hour, minute, second)?;
+    let today = now - now % SECONDS_PER_DAY;
+    let candidate = today as u64 + time as u64;
+    let next = if candidate > now as u64 {
+        candidate
+    } else {
+        candidate + SECONDS_PER_DAY as u64
+    };
+    u32::try_from(next).ok()
+}
+
+/// `now` 之后（不含 `now`）下一个每周 `weekday` 的 `hour:minute:second` 的时间戳
+/// 超出时间戳范围时返回 `None`
+pub fn next_weekly(now: u32, weekday: Weekday, hour: u8, minute: u8, second: u8) -> Option<u32> {
+    let time = time_of_day(hour, minute, second)?;
+    let days = now / SECONDS_PER_DAY;
+    let today = weekday_from_days(days.into()).days_from_monday();
+    let ahead = (weekday.days_from_monday() + 7 - today) % 7;
+
+    let mut candidate = (days + ahead) as u64 * SECONDS_PER_DAY as u64 + time as u64;
+    if candidate <= now as u64 {
+        candidate += 7 * SECONDS_PER_DAY as u64;
+    }
+    u32::try_from(candidate).ok()
+}
+
+fn time_of_day(hour: u8, minute: u8, second: u8) -> Option<u32> {
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some(hour as u32 * 3600 + minute as u32 * 60 + second as u32)
+}
+
+/// 1970-01-01 是星期四
+fn weekday_from_days(days: i64) -> Weekday {
+    match (days + 3).rem_euclid(7) {
+        0 => Weekday::Monday,
+        1 => Weekday::Tuesday,
+        2 => Weekday::Wednesday,
+        3 => Weekday::Thursday,
+        4 => Weekday::Friday,
+        5 => Weekday::Saturday,
+        _ => Weekday::Sunday,
+    }
+}
+
+/// 1970-01-01 起的天数，1970 年之前为负数
+/// 把 3 月作为一年的第一个月，闰日落在年末，按 400 年周期计算
+fn days_from_civil(year: u16, month: u8, day: u8) -> i64 {
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    // 0000-03-01 到 1970-01-01 的天数为 719468
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// 由 1970-01-01 起的天数得到 (年, 月, 日)
+fn civil_from_days(days: u32) -> (u16, u8, u8) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (year_of_era + era * 400 + (month <= 2) as u32) as u16;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    /// (年, 月, 日, 时, 分, 秒)
+    type Fields = (u16, u8, u8, u8, u8, u8);
+
+    /// (时间戳, 日期时间, 星期)
+    const KNOWN: [(u32, Fields, Weekday); 10] = [
+        (0, (1970, 1, 1, 0, 0, 0), Weekday::Thursday),
+        (86_399, (1970, 1, 1, 23, 59, 59), Weekday::Thursday),
+        (68_169_600, (1972, 2, 29, 0, 0, 0), Weekday::Tuesday),
+        (951_782_400, (2000, 2, 29, 0, 0, 0), Weekday::Tuesday),
+        (1_000_000_000, (2001, 9, 9, 1, 46, 40), Weekday::Sunday),
+        (1_234_567_890, (2009, 2, 13, 23, 31, 30), Weekday::Friday),
+        (1_709_208_000, (2024, 2, 29, 12, 0, 0), Weekday::Thursday),
+        (2_147_483_647, (2038, 1, 19, 3, 14, 7), Weekday::Tuesday),
+        (4_102_444_800, (2100, 1, 1, 0, 0, 0), Weekday::Friday),
+        (u32::MAX, (2106, 2, 7, 6, 28, 15), Weekday::Sunday),
+    ];
+
+    #[test]
+    fn known_timestamps() {
+        for (timestamp, (y, mo, d, h, mi, s), wd) in KNOWN {
+            let expected = datetime(y, mo, d, h, mi, s);
+            assert_eq!(DateTime::from_timestamp(timestamp), expected);
+            assert_eq!(expected.timestamp(), Ok(timestamp));
+            assert_eq!(expected.weekday(), wd);
+            assert_eq!(weekday(timestamp), wd);
+        }
+    }
+
+    #[test]
+    fn leap_years() {
+        assert!(is_leap_year(1972));
+        assert!(is_leap_year(2000));
+        assert!(is_leap_year(2024));
+        assert!(!is_leap_year(1970));
+        assert!(!is_leap_year(2100));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(
+            datetime(2100, 2, 29, 0, 0, 0).timestamp(),
+            Err(Error::InvalidDate)
+        );
+        assert_eq!(datetime(2000, 12, 31, 0, 0, 0).day_of_year(), 366);
+        assert_eq!(datetime(2001, 12, 31, 0, 0, 0).day_of_year(), 365);
+    }
+
+    #[test]
+    fn every_day_round_trips() {
+        // 逐日检查：日期连续递增，星期依次循环，与时间戳互相转换一致
+        let last_day = u32::MAX / SECONDS_PER_DAY;
+        let mut previous = DateTime::from_timestamp(0);
+        let mut previous_weekday = previous.weekday();
+        for days in 1..=last_day {
+            let timestamp = days * SECONDS_PER_DAY;
+            let current = DateTime::from_timestamp(timestamp);
+            assert_eq!(current.timestamp(), Ok(timestamp));
+            assert_eq!((current.hour, current.minute, current.second), (0, 0, 0));
+
+            if previous.day < days_in_month(previous.year, previous.month) {
+                assert_eq!(
+                    (current.year, current.month, current.day),
+                    (previous.year, previous.month, previous.day + 1)
+                );
+            } else if previous.month < 12 {
+                assert_eq!(
+                    (current.year, current.month, current.day),
+                    (previous.year, previous.month + 1, 1)
+                );
+            } else {
+                assert_eq!(
+                    (current.year, current.month, current.day),
+                    (previous.year + 1, 1, 1)
+                );
+            }
+
+            let weekday = current.weekday();
+            assert_eq!(weekday.number(), previous_weekday.number() % 7 + 1);
+
+            previous = current;
+            previous_weekday = weekday;
+        }
+        assert_eq!((previous.year, previous.month, previous.day), (2106, 2, 7));
+    }
+
+    #[test]
+    fn every_second_of_a_day_round_trips() {
+        let base = 951_782_400;
+        for seconds in 0..SECONDS_PER_DAY {
+            let datetime = DateTime::from_timestamp(base + seconds);
+            assert_eq!(datetime.seconds_of_day(), seconds);
+            assert_eq!(datetime.timestamp(), Ok(base + seconds));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        assert_eq!(DateTime::new(2024, 13, 1, 0, 0, 0), Err(Error::InvalidDate));
+        assert_eq!(DateTime::new(2024, 0, 1, 0, 0, 0), Err(Error::InvalidDate));
+        assert_eq!(DateTime::new(2024, 4, 31, 0, 0, 0), Err(Error::InvalidDate));
+        assert_eq!(DateTime::new(2024, 4, 0, 0, 0, 0), Err(Error::InvalidDate));
+        assert_eq!(DateTime::new(2024, 4, 1, 24, 0, 0), Err(Error::InvalidTime));
+        assert_eq!(DateTime::new(2024, 4, 1, 0, 60, 0), Err(Error::InvalidTime));
+        assert_eq!(DateTime::new(2024, 4, 1, 0, 0, 60), Err(Error::InvalidTime));
+        assert_eq!(DateTime::new(1969, 12, 31, 0, 0, 0), Err(Error::OutOfRange));
+        assert_eq!(DateTime::new(2107, 1, 1, 0, 0, 0), Err(Error::OutOfRange));
+        assert_eq!(DateTime::new(2106, 2, 7, 6, 28, 16), Err(Error::OutOfRange));
+        assert!(DateTime::new(2106, 2, 7, 6, 28, 15).is_ok());
+    }
+
+    #[test]
+    fn daily_alarm() {
+        // 2024-02-29 12:00:00
+        let now = 1_709_208_000;
+        // 当天稍后
+        assert_eq!(next_daily(now, 13, 0, 0), Some(now + 3600));
+        // 正好是现在则为明天
+        assert_eq!(next_daily(now, 12, 0, 0), Some(now + SECONDS_PER_DAY));
+        // 当天已过则为明天，跨月到 3 月 1 日
+        let next = next_daily(now, 7, 30, 0).unwrap();
+        assert_eq!(
+            DateTime::from_timestamp(next),
+            datetime(2024, 3, 1, 7, 30, 0)
+        );
+        assert_eq!(next_daily(now, 24, 0, 0), None);
+        assert_eq!(next_daily(u32::MAX, 0, 0, 0), None);
+    }
+
+    #[test]
+    fn weekly_alarm() {
+        // 2024-02-29 12:00:00 星期四
+        let now = 1_709_208_000;
+        let next = next_weekly(now, Weekday::Monday, 8, 0, 0).unwrap();
+        assert_eq!(
+            DateTime::from_timestamp(next),
+            datetime(2024, 3, 4, 8, 0, 0)
+        );
+        assert_eq!(weekday(next), Weekday::Monday);
+        // 当天稍后
+        let next = next_weekly(now, Weekday::Thursday, 18, 0, 0).unwrap();
+        assert_eq!(next, now + 6 * 3600);
+        // 当天已过则为下周
+        let next = next_weekly(now, Weekday::Thursday, 12, 0, 0).unwrap();
+        assert_eq!(next, now + 7 * SECONDS_PER_DAY);
+        // 每个星期都在 7 天之内
+        for number in 1..=7 {
+            let weekday = Weekday::from_number(number).unwrap();
+            let next = next_weekly(now, weekday, 0, 0, 0).unwrap();
+            assert!(next > now && next <= now + 7 * SECONDS_PER_DAY);
+            assert_eq!(DateTime::from_timestamp(next).weekday(), weekday);
+        }
+        assert_eq!(Weekday::from_number(0), None);
+        assert_eq!(Weekday::from_number(8), None);
+    }
+
+    #[test]
+    fn weekday_outside_timestamp_range() {
+        // 1970 年之前和 2106 年之后按公历推算
+        assert_eq!(
+            datetime(1969, 12, 31, 0, 0, 0).weekday(),
+            Weekday::Wednesday
+        );
+        assert_eq!(datetime(1900, 1, 1, 0, 0, 0).weekday(), Weekday::Monday);
+        assert_eq!(datetime(1600, 2, 29, 0, 0, 0).weekday(), Weekday::Tuesday);
+        assert_eq!(datetime(2200, 1, 1, 0, 0, 0).weekday(), Weekday::Wednesday);
+        // 0000-03-01 为星期三，前一天为星期二
+        assert_eq!(datetime(0, 3, 1, 0, 0, 0).weekday(), Weekday::Wednesday);
+        assert_eq!(datetime(0, 2, 29, 0, 0, 0).weekday(), Weekday::Tuesday);
+        // 不合法的字段也不会溢出
+        let _ = datetime(0, 1, 0, 0, 0, 0).weekday();
+        let _ = datetime(0, 0, 0, 0, 0, 0).weekday();
+        let _ = datetime(u16::MAX, 255, 255, 0, 0, 0).weekday();
+    }
+}
@@ -0,0 +1,138 @@
+//! 异步执行器
+//!
+//! 单核 `no_std` 执行器，不使用堆分配，最多同时运行 32 个任务。
+//! 任务的唤醒器由中断处理函数触发（SysTick/TIM2 驱动定时器，EXTIx 驱动引脚边沿），
+//! 没有任务就绪时执行 WFE 进入睡眠。
+//!
+//! ```rust
+//! let blink = pin!(blink_task(led));
+//! let key = pin!(key_task(key, exti));
+//! executor::run(&mut [blink, key]);
+//!
+//! #[exception]
+//! fn SysTick() {
+//!     executor::timer::on_tick();
+//! }
+//!
+//! #[interrupt]
+//! fn EXTI1() {
+//!     exti::on_interrupt();
+//! }
+//! ```
+
+pub mod timer;
+mod waker;
+
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+pub use waker::AtomicWaker;
+
+/// 最大任务数量
+pub const MAX_TASKS: usize = 32;
+
+/// 就绪任务位图，第 n 位表示第 n 个任务需要轮询
+static READY: AtomicU32 = AtomicU32::new(0);
+
+/// 运行一组任务，永不返回
+/// 已完成的任务不会再被轮询
+pub fn run(tasks: &mut [Pin<&mut dyn Future<Output = ()>>]) -> ! {
+    assert!(tasks.len() <= MAX_TASKS, "too many tasks");
+
+    // 所有任务先轮询一次
+    READY.store(task_mask(tasks.len()), Ordering::Release);
+    let mut done: u32 = 0;
+
+    loop {
+        let ready = READY.swap(0, Ordering::AcqRel);
+        if ready == 0 {
+            // 唤醒器会执行 SEV，即使在检查之后才唤醒也不会丢失事件
+            cortex_m::asm::wfe();
+            continue;
+        }
+
+        for (index, task) in tasks.iter_mut().enumerate() {
+            let bit = 1 << index;
+            if ready & bit == 0 || done & bit != 0 {
+                continue;
+            }
+
+            let waker = task_waker(index);
+            let mut cx = Context::from_waker(&waker);
+            if task.as_mut().poll(&mut cx).is_ready() {
+                done |= bit;
+            }
+        }
+    }
+}
+
+/// 运行单个 Future 直到完成并返回结果
+/// 与 `run` 共用就绪位图，不能在任务中调用
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let waker = task_waker(0);
+    let mut cx = Context::from_waker(&waker);
+
+    READY.store(1, Ordering::Release);
+    loop {
+        if READY.swap(0, Ordering::AcqRel) == 0 {
+            cortex_m::asm::wfe();
+            continue;
+        }
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// 让出执行权，下一轮再继续
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// `yield_now` 返回的 Future
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+fn task_mask(count: usize) -> u32 {
+    if count >= MAX_TASKS {
+        u32::MAX
+    } else {
+        (1 << count) - 1
+    }
+}
+
+/// 唤醒器的数据指针直接存放任务序号
+fn task_waker(index: usize) -> Waker {
+    unsafe { Waker::from_raw(RawWaker::new(index as *const (), &VTABLE)) }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+
+unsafe fn clone(data: *const ()) -> RawWaker {
+    RawWaker::new(data, &VTABLE)
+}
+
+unsafe fn wake(data: *const ()) {
+    READY.fetch_or(1 << data as usize, Ordering::AcqRel);
+    // 唤醒正在 WFE 中等待的执行器
+    cortex_m::asm::sev();
+}
+
+unsafe fn drop(_: *const ()) {}
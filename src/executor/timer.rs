@@ -0,0 +1,157 @@
+//! 异步定时器
+//!
+//! 节拍由 SysTick 异常或 TIMx 更新中断提供，在对应的处理函数中调用 `on_tick`，
+//! 到期的 `Timer` 会在节拍中断中唤醒所属任务。
+
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use cortex_m::interrupt::Mutex;
+use cortex_m::peripheral::SYST;
+
+use crate::hardware::syst::{self, start_tick};
+
+/// 可同时等待的定时器数量
+const QUEUE_LEN: usize = 8;
+
+/// 节拍计数
+static TICKS: AtomicU32 = AtomicU32::new(0);
+/// 节拍频率
+static TICK_HZ: AtomicU32 = AtomicU32::new(1000);
+/// 分配给下一个定时器的编号
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+/// 等待中的定时器：(定时器编号, 到期节拍, 唤醒器)
+type Queue = [Option<(u32, u32, Waker)>; QUEUE_LEN];
+static QUEUE: Mutex<RefCell<Queue>> = Mutex::new(RefCell::new([const { None }; QUEUE_LEN]));
+
+/// 使用 SysTick 作为节拍源，每秒 `hz` 个节拍
+/// 需要在 `SysTick` 异常处理函数中调用 `on_tick`
+pub fn start(syst: &mut SYST, hz: u32) -> Result<(), syst::Error> {
+    start_tick(syst, hz)?;
+    TICK_HZ.store(hz, Ordering::Relaxed);
+    Ok(())
+}
+
+/// 使用其它定时器（如 TIM2 更新中断）作为节拍源时，设置节拍频率
+pub fn set_tick_hz(hz: u32) {
+    TICK_HZ.store(hz, Ordering::Relaxed);
+}
+
+/// 当前节拍计数
+pub fn now() -> u32 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+/// 推进一个节拍并唤醒到期的定时器
+/// 在 SysTick 或 TIMx 更新中断的处理函数中调用
+pub fn on_tick() {
+    let now = TICKS.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+
+    cortex_m::interrupt::free(|cs| {
+        let mut queue = QUEUE.borrow(cs).borrow_mut();
+        for entry in queue.iter_mut() {
+            if matches!(entry, Some((_, deadline, _)) if is_due(now, *deadline)) {
+                if let Some((_, _, waker)) = entry.take() {
+                    waker.wake();
+                }
+            }
+        }
+    });
+}
+
+/// 毫秒换算为节拍数，向上取整
+pub fn ms_to_ticks(ms: u32) -> u32 {
+    let hz = TICK_HZ.load(Ordering::Relaxed) as u64;
+    (ms as u64 * hz).div_ceil(1000) as u32
+}
+
+/// 在指定节拍到期的 Future
+/// 丢弃时从等待队列中移除，不会在到期时唤醒已经不再等待的任务
+pub struct Timer {
+    /// 在等待队列中标识本定时器
+    id: u32,
+    deadline: u32,
+    /// 是否可能仍在等待队列中
+    queued: bool,
+}
+
+impl Timer {
+    /// 等待 `ticks` 个节拍
+    pub fn after(ticks: u32) -> Self {
+        Self::at(now().wrapping_add(ticks))
+    }
+
+    /// 等待 `ms` 毫秒
+    pub fn after_ms(ms: u32) -> Self {
+        Self::after(ms_to_ticks(ms))
+    }
+
+    /// 等待到节拍计数为 `deadline`
+    pub fn at(deadline: u32) -> Self {
+        Timer {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            deadline,
+            queued: false,
+        }
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if is_due(now(), self.deadline) {
+            return Poll::Ready(());
+        }
+
+        let (id, deadline) = (self.id, self.deadline);
+        let queued = cortex_m::interrupt::free(|cs| {
+            let mut queue = QUEUE.borrow(cs).borrow_mut();
+            // 重复轮询时更新原有的条目
+            let slot = queue
+                .iter()
+                .position(|entry| matches!(entry, Some((entry_id, _, _)) if *entry_id == id))
+                .or_else(|| queue.iter().position(|entry| entry.is_none()));
+
+            match slot {
+                Some(index) => {
+                    queue[index] = Some((id, deadline, cx.waker().clone()));
+                    true
+                }
+                None => false,
+            }
+        });
+        self.queued |= queued;
+
+        // 队列已满时退化为轮询
+        if !queued {
+            cx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if !self.queued {
+            return;
+        }
+        let id = self.id;
+        cortex_m::interrupt::free(|cs| {
+            let mut queue = QUEUE.borrow(cs).borrow_mut();
+            for entry in queue.iter_mut() {
+                if matches!(entry, Some((entry_id, _, _)) if *entry_id == id) {
+                    *entry = None;
+                }
+            }
+        });
+    }
+}
+
+/// 判断是否已到期，兼容节拍计数回绕
+fn is_due(now: u32, deadline: u32) -> bool {
+    now.wrapping_sub(deadline) as i32 >= 0
+}
@@ -0,0 +1,50 @@
+//! 中断与任务之间共享的唤醒器
+
+use core::cell::RefCell;
+use core::task::Waker;
+
+use cortex_m::interrupt::Mutex;
+
+/// 保存一个唤醒器，任务在等待前注册，中断处理函数中唤醒
+pub struct AtomicWaker {
+    waker: Mutex<RefCell<Option<Waker>>>,
+}
+
+impl Default for AtomicWaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AtomicWaker {
+    /// 创建空的唤醒器，用于 `static` 初始化
+    pub const fn new() -> Self {
+        AtomicWaker {
+            waker: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// 注册唤醒器，替换之前注册的唤醒器
+    pub fn register(&self, waker: &Waker) {
+        cortex_m::interrupt::free(|cs| {
+            let mut slot = self.waker.borrow(cs).borrow_mut();
+            match slot.as_ref() {
+                Some(old) if old.will_wake(waker) => {}
+                _ => *slot = Some(waker.clone()),
+            }
+        });
+    }
+
+    /// 唤醒并移除已注册的唤醒器
+    pub fn wake(&self) {
+        let waker = cortex_m::interrupt::free(|cs| self.waker.borrow(cs).borrow_mut().take());
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+    /// 移除已注册的唤醒器，不唤醒
+    /// 等待方被丢弃时调用，避免之后的中断唤醒无关的任务
+    pub fn clear(&self) {
+        cortex_m::interrupt::free(|cs| self.waker.borrow(cs).borrow_mut().take());
+    }
+}
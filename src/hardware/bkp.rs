@@ -0,0 +1,312 @@
+//! 备份寄存器（BKP）
+//!
+//! 备份域中的 16 位数据寄存器在系统复位、待机唤醒后保持不变，VBAT 供电时主电源掉电也不会丢失：
+//! - 中小容量产品有 10 个（DR1~DR10），大容量和互联型产品有 42 个（DR1~DR42）
+//! - 写入前需要使能 PWR、BKP 时钟并置位 PWR_CR 的 DBP
+//! - 备份域复位（VBAT 掉电、BDRST）后全部清零，可以写入标记值检测
+//! - 侵入检测（TAMPER，PC13）有效时，硬件自动清除所有数据寄存器
+//!
+//! 本模块把 DR1 用作备份域有效标记，`domain_lost`/`mark_valid` 使用它，用户数据从序号 1 开始，
+//! 写入序号 0 会返回 `Error::Reserved`。
+//!
+//! ```rust
+//! let mut backup = Backup::new(dp.BKP, rcc, pwr);
+//! if backup.domain_lost() {
+//!     backup.write(1, 0);
+//!     backup.mark_valid();
+//! }
+//! let boots = backup.read(1) + 1;
+//! backup.write(1, boots);
+//! ```
+
+use stm32f1::stm32f103::{BKP, PWR, RCC};
+
+use super::rtc::{enable_backup_domain, MAX_CALIBRATION};
+
+/// 中小容量产品的数据寄存器数量
+pub const LOW_DENSITY_REGISTERS: usize = 10;
+/// 大容量和互联型产品的数据寄存器数量
+pub const HIGH_DENSITY_REGISTERS: usize = 42;
+/// 备份域有效标记所在的寄存器序号（DR1）
+pub const MARKER_INDEX: usize = 0;
+/// 备份域有效标记
+pub const MARKER: u16 = 0xA5A5;
+
+/// 记录最大字节数，头部占用一个寄存器
+const MAX_RECORD_BYTES: usize = (HIGH_DENSITY_REGISTERS - 1) * 2;
+
+/// 备份寄存器错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 序号超出寄存器数量
+    OutOfRange,
+    /// 寄存器被备份域有效标记占用
+    Reserved,
+    /// 记录的长度或校验和不匹配，数据不存在或已损坏
+    InvalidRecord,
+}
+
+/// 侵入检测有效电平
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TamperLevel {
+    /// 高电平有效
+    High,
+    /// 低电平有效
+    Low,
+}
+
+/// 可以保存到备份寄存器中的数据
+pub trait Persist: Sized {
+    /// 编码后的字节数
+    const SIZE: usize;
+
+    /// 编码到 `buf`，`buf` 的长度为 `SIZE`
+    fn encode(&self, buf: &mut [u8]);
+
+    /// 从 `buf` 解码，`buf` 的长度为 `SIZE`
+    fn decode(buf: &[u8]) -> Option<Self>;
+}
+
+/// 备份寄存器
+pub struct Backup {
+    bkp: BKP,
+    registers: usize,
+}
+
+impl Backup {
+    /// 使能备份域访问，中小容量产品（10 个数据寄存器）
+    pub fn new(bkp: BKP, rcc: &RCC, pwr: &PWR) -> Self {
+        enable_backup_domain(rcc, pwr);
+        Backup {
+            bkp,
+            registers: LOW_DENSITY_REGISTERS,
+        }
+    }
+
+    /// 使能备份域访问，大容量和互联型产品（42 个数据寄存器）
+    pub fn new_high_density(bkp: BKP, rcc: &RCC, pwr: &PWR) -> Self {
+        enable_backup_domain(rcc, pwr);
+        Backup {
+            bkp,
+            registers: HIGH_DENSITY_REGISTERS,
+        }
+    }
+
+    /// 释放 BKP 外设
+    pub fn release(self) -> BKP {
+        self.bkp
+    }
+
+    /// 数据寄存器数量
+    pub fn len(&self) -> usize {
+        self.registers
+    }
+
+    /// 是否没有数据寄存器
+    pub fn is_empty(&self) -> bool {
+        self.registers == 0
+    }
+
+    /// 读取数据寄存器，`index` 从 0 开始（DR1）
+    /// 序号超出范围时 panic
+    pub fn read(&self, index: usize) -> u16 {
+        self.try_read(index)
+            .expect("backup register index out of range")
+    }
+
+    /// 写入数据寄存器，`index` 从 1 开始（DR2），DR1 保存备份域有效标记
+    /// 序号超出范围或为 0 时 panic
+    pub fn write(&mut self, index: usize, value: u16) {
+        self.try_write(index, value)
+            .expect("backup register index out of range or reserved");
+    }
+
+    /// 读取数据寄存器
+    pub fn try_read(&self, index: usize) -> Result<u16, Error> {
+        let bkp = &self.bkp;
+        match index {
+            _ if index >= self.registers => Err(Error::OutOfRange),
+            0..=9 => Ok(bkp.dr[index].read().bits() as u16),
+            _ => Ok(bkp.bkp_dr[index - 10].read().bits() as u16),
+        }
+    }
+
+    /// 写入数据寄存器
+    /// 序号 0 为备份域有效标记，返回 `Error::Reserved`
+    pub fn try_write(&mut self, index: usize, value: u16) -> Result<(), Error> {
+        let bkp = &self.bkp;
+        match index {
+            _ if index == MARKER_INDEX => return Err(Error::Reserved),
+            _ if index >= self.registers => return Err(Error::OutOfRange),
+            0..=9 => bkp.dr[index].write(|w| unsafe { w.bits(value as u32) }),
+            _ => bkp.bkp_dr[index - 10].write(|w| unsafe { w.bits(value as u32) }),
+        }
+        Ok(())
+    }
+
+    /// 读取占用 `index`、`index + 1` 两个寄存器的 32 位数据，低 16 位在前
+    pub fn read_u32(&self, index: usize) -> Result<u32, Error> {
+        let low = self.try_read(index)? as u32;
+        let high = self.try_read(index + 1)? as u32;
+        Ok(high << 16 | low)
+    }
+
+    /// 写入占用 `index`、`index + 1` 两个寄存器的 32 位数据，低 16 位在前
+    pub fn write_u32(&mut self, index: usize, value: u32) -> Result<(), Error> {
+        self.try_read(index + 1)?;
+        self.try_write(index, value as u16)?;
+        self.try_write(index + 1, (value >> 16) as u16)
+    }
+
+    /// 备份域是否复位过（标记不存在）
+    pub fn domain_lost(&self) -> bool {
+        self.read(MARKER_INDEX) != MARKER
+    }
+
+    /// 写入备份域有效标记
+    pub fn mark_valid(&mut self) {
+        self.bkp.dr[MARKER_INDEX].write(|w| unsafe { w.bits(MARKER as u32) });
+    }
+
+    /// 把 `value` 保存到从 `index` 开始的寄存器
+    /// 第一个寄存器保存长度和校验和，之后每个寄存器保存 2 个字节
+    pub fn store<T: Persist>(&mut self, index: usize, value: &T) -> Result<(), Error> {
+        if index == MARKER_INDEX {
+            return Err(Error::Reserved);
+        }
+        let mut buf = [0u8; MAX_RECORD_BYTES];
+        let bytes = record_bytes::<T>(&mut buf)?;
+        self.check_record_range(index, bytes.len())?;
+
+        value.encode(bytes);
+        for (i, chunk) in bytes.chunks(2).enumerate() {
+            let word = chunk[0] as u16 | (*chunk.get(1).unwrap_or(&0) as u16) << 8;
+            self.try_write(index + 1 + i, word)?;
+        }
+        self.try_write(index, header(bytes))
+    }
+
+    /// 读取 `store` 保存的数据
+    /// 备份域复位或数据损坏时返回 `Error::InvalidRecord`
+    pub fn load<T: Persist>(&self, index: usize) -> Result<T, Error> {
+        let mut buf = [0u8; MAX_RECORD_BYTES];
+        let bytes = record_bytes::<T>(&mut buf)?;
+        self.check_record_range(index, bytes.len())?;
+
+        let len = bytes.len();
+        for i in 0..len.div_ceil(2) {
+            let word = self.try_read(index + 1 + i)?;
+            bytes[i * 2] = word as u8;
+            if i * 2 + 1 < len {
+                bytes[i * 2 + 1] = (word >> 8) as u8;
+            }
+        }
+        if self.try_read(index)? != header(bytes) {
+            return Err(Error::InvalidRecord);
+        }
+        T::decode(bytes).ok_or(Error::InvalidRecord)
+    }
+
+    /// 使能侵入检测
+    /// TAMPER 引脚（PC13）出现有效电平时，硬件清除所有数据寄存器并置位 TEF；
+    /// `interrupt` 为 true 时同时产生 TAMPER 中断
+    pub fn enable_tamper(&mut self, level: TamperLevel, interrupt: bool) {
+        let bkp = &self.bkp;
+        // 先关闭，再设置有效电平，避免修改 TPAL 时产生误触发
+        bkp.cr.modify(|_, w| w.tpe().clear_bit());
+        bkp.cr
+            .modify(|_, w| w.tpal().bit(level == TamperLevel::Low));
+        bkp.csr
+            .modify(|_, w| w.cte().set_bit().cti().set_bit().tpie().bit(interrupt));
+        bkp.cr.modify(|_, w| w.tpe().set_bit());
+    }
+
+    /// 关闭侵入检测，PC13 恢复为普通 IO
+    pub fn disable_tamper(&mut self) {
+        let bkp = &self.bkp;
+        bkp.csr.modify(|_, w| w.tpie().clear_bit());
+        bkp.cr.modify(|_, w| w.tpe().clear_bit());
+    }
+
+    /// 是否发生了侵入事件
+    pub fn is_tamper_event(&self) -> bool {
+        self.bkp.csr.read().tef().bit_is_set()
+    }
+
+    /// 清除侵入事件和中断标志
+    /// 清除前 TEF 保持置位，数据寄存器一直处于复位状态且不能写入
+    pub fn clear_tamper(&mut self) {
+        self.bkp
+            .csr
+            .modify(|_, w| w.cte().set_bit().cti().set_bit());
+    }
+
+    /// 设置 RTC 校准值 CAL（0~127）
+    /// 每 2^20 个 RTCCLK 脉冲去掉 CAL 个，可由 `rtc::ppm_to_calibration` 计算
+    pub fn set_rtc_calibration(&mut self, calibration: u8) {
+        self.bkp
+            .rtccr
+            .modify(|_, w| unsafe { w.cal().bits(calibration & MAX_CALIBRATION) });
+    }
+
+    /// 当前的 RTC 校准值
+    pub fn rtc_calibration(&self) -> u8 {
+        self.bkp.rtccr.read().cal().bits()
+    }
+
+    /// 在 TAMPER 引脚（PC13）输出 RTCCLK/64 的校准时钟，用于测量 RTC 时钟误差
+    /// 与侵入检测共用引脚，这里会关闭侵入检测
+    pub fn enable_calibration_output(&mut self) {
+        self.disable_tamper();
+        self.bkp.rtccr.modify(|_, w| w.cco().set_bit());
+    }
+
+    /// 关闭校准时钟输出
+    pub fn disable_calibration_output(&mut self) {
+        self.bkp.rtccr.modify(|_, w| w.cco().clear_bit());
+    }
+
+    fn check_record_range(&self, index: usize, len: usize) -> Result<(), Error> {
+        if index + 1 + len.div_ceil(2) > self.registers {
+            return Err(Error::OutOfRange);
+        }
+        Ok(())
+    }
+}
+
+/// 取出 `T` 所需长度的缓冲区
+fn record_bytes<T: Persist>(buf: &mut [u8; MAX_RECORD_BYTES]) -> Result<&mut [u8], Error> {
+    buf.get_mut(..T::SIZE).ok_or(Error::OutOfRange)
+}
+
+/// 记录头：高 8 位为长度，低 8 位为校验和
+fn header(bytes: &[u8]) -> u16 {
+    let checksum = bytes
+        .iter()
+        .fold(0xA5u8, |sum, &byte| sum.rotate_left(1) ^ byte);
+    (bytes.len() as u16) << 8 | checksum as u16
+}
+
+impl Persist for u16 {
+    const SIZE: usize = 2;
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(buf: &[u8]) -> Option<Self> {
+        Some(u16::from_le_bytes(buf.try_into().ok()?))
+    }
+}
+
+impl Persist for u32 {
+    const SIZE: usize = 4;
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(buf: &[u8]) -> Option<Self> {
+        Some(u32::from_le_bytes(buf.try_into().ok()?))
+    }
+}
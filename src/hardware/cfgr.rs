@@ -2,7 +2,28 @@
 
 use stm32f1::stm32f103::RCC;
 
+use super::delay;
+
+/// 内部高速时钟（HSI）频率
+pub const HSI_HZ: u32 = 8_000_000;
+/// 外部高速时钟（HSE）频率，开发板上的晶振为 8MHz
+pub const HSE_HZ: u32 = 8_000_000;
+/// `set_clock` 配置后的系统时钟频率
+pub const SYSCLK_HZ: u32 = 72_000_000;
+/// `set_clock` 配置后的 AHB 总线时钟频率
+pub const HCLK_HZ: u32 = SYSCLK_HZ;
+/// `set_clock` 配置后的 APB1 总线时钟频率
+pub const PCLK1_HZ: u32 = HCLK_HZ / 2;
+/// `set_clock` 配置后的 APB2 总线时钟频率
+pub const PCLK2_HZ: u32 = HCLK_HZ;
+/// APB1 上定时器（TIM2~TIM7）的时钟频率
+/// APB1 分频系数不为 1 时，定时器时钟为 PCLK1 的 2 倍
+pub const TIMCLK1_HZ: u32 = PCLK1_HZ * 2;
+/// APB2 上定时器（TIM1、TIM8）的时钟频率
+pub const TIMCLK2_HZ: u32 = PCLK2_HZ;
+
 /// 设置时钟
+/// 切换到 72MHz PLL 后返回，并更新忙等待延时使用的系统时钟频率
 pub fn set_clock(rcc: &RCC) {
     // 启用高速外部时钟（HSE）
     rcc.cr.modify(|_, w| w.hseon().set_bit());
@@ -37,4 +58,38 @@ pub fn set_clock(rcc: &RCC) {
     rcc.cr.modify(|_, w| w.pllon().set_bit());
     // 等待 PLL 稳定
     while rcc.cr.read().pllrdy().bit_is_clear() {}
+    // 等待系统时钟切换到 PLL
+    while !rcc.cfgr.read().sws().is_pll() {}
+
+    // 更新延时使用的系统时钟频率
+    delay::init(rcc);
+}
+
+/// 根据 RCC 寄存器计算当前的系统时钟频率
+/// 外部晶振频率按 `HSE_HZ` 计算
+pub fn sysclk_hz(rcc: &RCC) -> u32 {
+    let cfgr = rcc.cfgr.read();
+
+    // 系统时钟切换状态
+    if cfgr.sws().is_hsi() {
+        return HSI_HZ;
+    }
+    if cfgr.sws().is_hse() {
+        return HSE_HZ;
+    }
+
+    // PLL 输入时钟
+    let pll_input = if cfgr.pllsrc().bit_is_clear() {
+        // HSI 二分频
+        HSI_HZ / 2
+    } else if cfgr.pllxtpre().bit_is_set() {
+        // HSE 二分频
+        HSE_HZ / 2
+    } else {
+        HSE_HZ
+    };
+
+    // PLL 倍频系数：0b0000 表示 2 倍，依次递增，最大为 16 倍
+    let pllmul = (cfgr.pllmul().bits() as u32 + 2).min(16);
+    pll_input * pllmul
 }
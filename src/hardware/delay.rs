@@ -0,0 +1,121 @@
+//! 忙等待延时
+//!
+//! 使用内联汇编实现的计数循环，每次迭代的时钟周期数已知，
+//! 根据当前配置的系统时钟换算出周期数，可选在启动时用 DWT 周期计数器校验。
+//!
+//! ```rust
+//! // set_clock 切换时钟后会更新延时使用的系统时钟频率
+//! set_clock(rcc);
+//! // 可选：用 DWT 测量每次迭代的实际周期数
+//! delay::verify(&mut cp.DCB, &mut cp.DWT);
+//!
+//! delay::delay_us(10);
+//! ```
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use cortex_m::peripheral::{DCB, DWT};
+use stm32f1::stm32f103::RCC;
+
+use super::cfgr::{sysclk_hz, HSI_HZ};
+
+/// 每次循环迭代的标称时钟周期数
+/// `subs` 1 个周期，`bne` 跳转成功时 2 个周期
+pub const CYCLES_PER_ITERATION: u32 = 3;
+
+/// 校验时执行的迭代次数
+const VERIFY_ITERATIONS: u32 = 10_000;
+
+/// 系统时钟频率，复位后默认为 HSI
+static SYSCLK: AtomicU32 = AtomicU32::new(HSI_HZ);
+/// 每次迭代的时钟周期数
+static LOOP_CYCLES: AtomicU32 = AtomicU32::new(CYCLES_PER_ITERATION);
+
+/// 根据 RCC 当前的时钟配置更新延时使用的系统时钟频率
+/// `set_clock` 会自动调用；用其它方式修改时钟树后需要手动调用
+pub fn init(rcc: &RCC) {
+    SYSCLK.store(sysclk_hz(rcc), Ordering::Relaxed);
+}
+
+/// 使用 DWT 周期计数器测量每次迭代的实际时钟周期数
+/// Flash 等待周期会影响跳转的耗时，测量结果会替换标称值并返回
+pub fn verify(dcb: &mut DCB, dwt: &mut DWT) -> u32 {
+    // 启用 DWT 周期计数器
+    dcb.enable_trace();
+    dwt.enable_cycle_counter();
+
+    let start = DWT::cycle_count();
+    spin(VERIFY_ITERATIONS);
+    let cycles = DWT::cycle_count().wrapping_sub(start);
+
+    // 四舍五入
+    let per_iteration = ((cycles + VERIFY_ITERATIONS / 2) / VERIFY_ITERATIONS).max(1);
+    LOOP_CYCLES.store(per_iteration, Ordering::Relaxed);
+    per_iteration
+}
+
+/// 当前使用的系统时钟频率
+pub fn sysclk() -> u32 {
+    SYSCLK.load(Ordering::Relaxed)
+}
+
+/// 当前使用的每次迭代时钟周期数
+pub fn cycles_per_iteration() -> u32 {
+    LOOP_CYCLES.load(Ordering::Relaxed)
+}
+
+/// 延时指定的时钟周期数
+/// 实际延时不少于 `cycles`，中断会使延时变长
+pub fn delay_cycles(cycles: u32) {
+    let per_iteration = cycles_per_iteration();
+    spin(cycles.div_ceil(per_iteration));
+}
+
+/// 微秒延时
+pub fn delay_us(us: u32) {
+    let cycles = us as u64 * (sysclk() / 1_000_000) as u64;
+    delay_cycles_u64(cycles);
+}
+
+/// 毫秒延时
+pub fn delay_ms(ms: u32) {
+    let cycles = ms as u64 * (sysclk() / 1_000) as u64;
+    delay_cycles_u64(cycles);
+}
+
+/// 延时超过 `u32` 范围的周期数时分段执行
+fn delay_cycles_u64(mut cycles: u64) {
+    while cycles > u32::MAX as u64 {
+        delay_cycles(u32::MAX);
+        cycles -= u32::MAX as u64;
+    }
+    delay_cycles(cycles as u32);
+}
+
+/// 计数循环
+/// 按 8 字节对齐，使循环体落在同一个 Flash 预取行中，迭代耗时稳定
+#[cfg(target_arch = "arm")]
+#[inline(always)]
+fn spin(iterations: u32) {
+    if iterations == 0 {
+        return;
+    }
+    unsafe {
+        core::arch::asm!(
+            ".p2align 3",
+            "2:",
+            "subs {0}, #1",
+            "bne 2b",
+            inout(reg) iterations => _,
+            options(nomem, nostack),
+        );
+    }
+}
+
+/// 非 ARM 目标（主机编译）上的占位实现
+#[cfg(not(target_arch = "arm"))]
+fn spin(iterations: u32) {
+    for _ in 0..iterations {
+        core::hint::spin_loop();
+    }
+}
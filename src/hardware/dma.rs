@@ -0,0 +1,116 @@
+//! DMA1 通道
+//!
+//! DMA1 有 7 个通道，每个通道连接固定的外设请求，例如：
+//! - USART1: TX 通道 4、RX 通道 5
+//! - USART2: TX 通道 7、RX 通道 6
+//! - USART3: TX 通道 2、RX 通道 3
+//!
+//! 每个通道在 ISR/IFCR 中占 4 位：GIF、TCIF、HTIF、TEIF。
+
+use stm32f1::stm32f103::{dma1, Interrupt, DMA1, RCC};
+
+/// DMA1 通道
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmaChannel {
+    C1,
+    C2,
+    C3,
+    C4,
+    C5,
+    C6,
+    C7,
+}
+
+/// 通道标志
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    /// 传输完成
+    TransferComplete,
+    /// 传输过半
+    HalfTransfer,
+    /// 传输错误
+    TransferError,
+}
+
+impl Flag {
+    /// 在通道的 4 位标志中的偏移
+    fn offset(self) -> u32 {
+        match self {
+            Flag::TransferComplete => 1,
+            Flag::HalfTransfer => 2,
+            Flag::TransferError => 3,
+        }
+    }
+}
+
+impl DmaChannel {
+    /// 通道序号，通道 1 为 0
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// 通道寄存器组
+    pub fn regs(self) -> &'static dma1::CH {
+        let dma1 = unsafe { &*DMA1::ptr() };
+        match self {
+            DmaChannel::C1 => &dma1.ch1,
+            DmaChannel::C2 => &dma1.ch2,
+            DmaChannel::C3 => &dma1.ch3,
+            DmaChannel::C4 => &dma1.ch4,
+            DmaChannel::C5 => &dma1.ch5,
+            DmaChannel::C6 => &dma1.ch6,
+            DmaChannel::C7 => &dma1.ch7,
+        }
+    }
+
+    /// 通道中断号
+    pub fn interrupt(self) -> Interrupt {
+        match self {
+            DmaChannel::C1 => Interrupt::DMA1_CHANNEL1,
+            DmaChannel::C2 => Interrupt::DMA1_CHANNEL2,
+            DmaChannel::C3 => Interrupt::DMA1_CHANNEL3,
+            DmaChannel::C4 => Interrupt::DMA1_CHANNEL4,
+            DmaChannel::C5 => Interrupt::DMA1_CHANNEL5,
+            DmaChannel::C6 => Interrupt::DMA1_CHANNEL6,
+            DmaChannel::C7 => Interrupt::DMA1_CHANNEL7,
+        }
+    }
+
+    /// 标志是否置位
+    pub fn is_flag_set(self, flag: Flag) -> bool {
+        let dma1 = unsafe { &*DMA1::ptr() };
+        let bit = 4 * self.index() as u32 + flag.offset();
+        dma1.isr.read().bits() & (1 << bit) != 0
+    }
+
+    /// 清除标志
+    pub fn clear_flag(self, flag: Flag) {
+        let dma1 = unsafe { &*DMA1::ptr() };
+        let bit = 4 * self.index() as u32 + flag.offset();
+        dma1.ifcr.write(|w| unsafe { w.bits(1 << bit) });
+    }
+
+    /// 清除通道的所有标志
+    pub fn clear_flags(self) {
+        let dma1 = unsafe { &*DMA1::ptr() };
+        // GIF 位于通道 4 位标志的最低位，清除它会同时清除其它标志
+        dma1.ifcr
+            .write(|w| unsafe { w.bits(1 << (4 * self.index() as u32)) });
+    }
+
+    /// 剩余的传输数量
+    pub fn remaining(self) -> u16 {
+        self.regs().ndtr.read().ndt().bits()
+    }
+
+    /// 关闭通道，并清除所有标志
+    pub fn stop(self) {
+        self.regs().cr.modify(|_, w| w.en().disabled());
+        self.clear_flags();
+    }
+}
+
+/// 使能 AHB DMA1 时钟
+pub fn enable_clock(rcc: &RCC) {
+    rcc.ahbenr.modify(|_, w| w.dma1en().set_bit());
+}
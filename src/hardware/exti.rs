@@ -0,0 +1,282 @@
+//! 外部中断/事件控制器（EXTI）
+//!
+//! GPIO 引脚 Pxn 固定连接到 EXTI 线 n，同一条线上同一时刻只能选择一个端口，
+//! 由 AFIO 的 EXTICR1~EXTICR4 寄存器选择（每条线 4 位，0 表示 PA，1 表示 PB，以此类推）。
+//!
+//! ```rust
+//! let mut key = Gpiob::new(gpiob, 1);
+//! // 选择 PB1 作为 EXTI1 的中断源
+//! key.make_interrupt_source(afio);
+//! key.trigger_on_edge(exti, Edge::Falling);
+//! key.enable_interrupt(exti);
+//!
+//! unsafe { NVIC::unmask(key.interrupt()) };
+//!
+//! // 中断处理函数中
+//! if key.check_interrupt() {
+//!     key.clear_interrupt_pending_bit();
+//! }
+//!
+//! // 异步任务中等待下降沿，EXTIx 中断处理函数中需要调用 `on_interrupt`
+//! key.wait_for_falling_edge(exti).await;
+//! ```
+
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::task::{Context, Poll};
+
+use stm32f1::stm32f103::{Interrupt, AFIO, EXTI};
+
+use super::gpio::{Gpioa, Gpiob};
+use crate::executor::AtomicWaker;
+
+/// 连接 GPIO 的 EXTI 线数量
+const GPIO_LINES: usize = 16;
+
+/// 由异步任务等待的线
+static ASYNC_LINES: AtomicU32 = AtomicU32::new(0);
+/// 已触发、尚未被任务取走的线
+static FIRED: AtomicU32 = AtomicU32::new(0);
+/// 每条线上等待的任务
+static WAKERS: [AtomicWaker; GPIO_LINES] = [const { AtomicWaker::new() }; GPIO_LINES];
+
+/// 触发边沿
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// 上升沿
+    Rising,
+    /// 下降沿
+    Falling,
+    /// 上升沿和下降沿
+    Both,
+}
+
+/// 可作为外部中断源的输入引脚
+pub trait ExtiPin {
+    /// 端口序号，PA 为 0，PB 为 1，以此类推
+    fn port_index(&self) -> u8;
+
+    /// 引脚号，即连接的 EXTI 线号
+    fn line(&self) -> u8;
+
+    /// 通过 AFIO 选择该引脚作为对应 EXTI 线的中断源
+    /// 需要先使能 AFIO 时钟
+    fn make_interrupt_source(&mut self, afio: &AFIO) {
+        select_source(afio, self.line(), self.port_index());
+    }
+
+    /// 设置触发边沿
+    fn trigger_on_edge(&mut self, exti: &EXTI, edge: Edge) {
+        set_trigger(exti, self.line(), edge);
+    }
+
+    /// 使能该线的中断
+    fn enable_interrupt(&mut self, exti: &EXTI) {
+        let mask = 1 << self.line();
+        exti.imr.modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+    }
+
+    /// 屏蔽该线的中断
+    fn disable_interrupt(&mut self, exti: &EXTI) {
+        let mask = 1 << self.line();
+        exti.imr.modify(|r, w| unsafe { w.bits(r.bits() & !mask) });
+    }
+
+    /// 该线的中断是否挂起
+    /// 只读取挂起寄存器，可以在中断处理函数中直接调用
+    fn check_interrupt(&self) -> bool {
+        is_pending(self.line())
+    }
+
+    /// 清除该线的挂起标志
+    fn clear_interrupt_pending_bit(&mut self) {
+        clear_pending(self.line());
+    }
+
+    /// 该线对应的 NVIC 中断
+    fn interrupt(&self) -> Interrupt {
+        line_interrupt(self.line())
+    }
+
+    /// 异步等待上升沿
+    fn wait_for_rising_edge(&mut self, exti: &EXTI) -> EdgeFuture {
+        self.wait_for_edge(exti, Edge::Rising)
+    }
+
+    /// 异步等待下降沿
+    fn wait_for_falling_edge(&mut self, exti: &EXTI) -> EdgeFuture {
+        self.wait_for_edge(exti, Edge::Falling)
+    }
+
+    /// 异步等待任意边沿
+    fn wait_for_any_edge(&mut self, exti: &EXTI) -> EdgeFuture {
+        self.wait_for_edge(exti, Edge::Both)
+    }
+
+    /// 配置触发边沿并使能中断，返回等待该边沿的 Future
+    /// 需要先调用 `make_interrupt_source`，并在 EXTIx 中断处理函数中调用 `on_interrupt`
+    fn wait_for_edge(&mut self, exti: &EXTI, edge: Edge) -> EdgeFuture {
+        let line = self.line();
+        let mask = 1 << line;
+
+        // 丢弃之前残留的触发
+        FIRED.fetch_and(!mask, Ordering::AcqRel);
+        ASYNC_LINES.fetch_or(mask, Ordering::AcqRel);
+
+        self.trigger_on_edge(exti, edge);
+        self.clear_interrupt_pending_bit();
+        self.enable_interrupt(exti);
+
+        EdgeFuture { line }
+    }
+}
+
+/// 等待引脚边沿的 Future
+/// 丢弃时移除该线上注册的唤醒器
+#[must_use = "futures do nothing unless polled"]
+pub struct EdgeFuture {
+    line: u8,
+}
+
+impl Future for EdgeFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mask = 1 << self.line;
+        // 先注册再检查，避免丢失注册之前发生的触发
+        WAKERS[self.line as usize].register(cx.waker());
+        if FIRED.fetch_and(!mask, Ordering::AcqRel) & mask != 0 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for EdgeFuture {
+    fn drop(&mut self) {
+        WAKERS[self.line as usize].clear();
+    }
+}
+
+/// 唤醒等待引脚边沿的任务
+/// 在 EXTI0~EXTI4、EXTI9_5、EXTI15_10 中断处理函数中调用，
+/// 只处理由异步任务等待的线，其它线的挂起标志保持不变
+pub fn on_interrupt() {
+    let exti = unsafe { &*EXTI::ptr() };
+    let pending = exti.pr.read().bits() & ASYNC_LINES.load(Ordering::Acquire);
+    if pending == 0 {
+        return;
+    }
+
+    // 挂起寄存器写 1 清除
+    exti.pr.write(|w| unsafe { w.bits(pending) });
+    FIRED.fetch_or(pending, Ordering::AcqRel);
+
+    for (line, waker) in WAKERS.iter().enumerate() {
+        if pending & (1 << line) != 0 {
+            waker.wake();
+        }
+    }
+}
+
+impl<'a> ExtiPin for Gpioa<'a> {
+    fn port_index(&self) -> u8 {
+        0
+    }
+
+    fn line(&self) -> u8 {
+        self.pin() as u8
+    }
+}
+
+impl<'a> ExtiPin for Gpiob<'a> {
+    fn port_index(&self) -> u8 {
+        1
+    }
+
+    fn line(&self) -> u8 {
+        self.pin() as u8
+    }
+}
+
+/// EXTI 线对应的 NVIC 中断
+/// 线 0~4 各自独占一个中断，线 5~9 共用 EXTI9_5，线 10~15 共用 EXTI15_10
+pub fn line_interrupt(line: u8) -> Interrupt {
+    match line {
+        0 => Interrupt::EXTI0,
+        1 => Interrupt::EXTI1,
+        2 => Interrupt::EXTI2,
+        3 => Interrupt::EXTI3,
+        4 => Interrupt::EXTI4,
+        5..=9 => Interrupt::EXTI9_5,
+        10..=15 => Interrupt::EXTI15_10,
+        _ => panic!("EXTI line {} is not connected to a GPIO pin", line),
+    }
+}
+
+/// 选择 EXTI 线的端口
+fn select_source(afio: &AFIO, line: u8, port: u8) {
+    // 每个 EXTICR 寄存器配置 4 条线，每条线占 4 位
+    let shift = (line % 4) * 4;
+    let mask = 0b1111 << shift;
+    let value = (port as u32) << shift;
+
+    match line / 4 {
+        0 => afio
+            .exticr1
+            .modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) }),
+        1 => afio
+            .exticr2
+            .modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) }),
+        2 => afio
+            .exticr3
+            .modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) }),
+        _ => afio
+            .exticr4
+            .modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) }),
+    }
+}
+
+/// 设置 EXTI 线的触发边沿
+/// 也用于 PVD（线 16）、RTC 闹钟（线 17）等内部线
+pub fn set_trigger(exti: &EXTI, line: u8, edge: Edge) {
+    let mask = 1 << line;
+    let (rising, falling) = match edge {
+        Edge::Rising => (true, false),
+        Edge::Falling => (false, true),
+        Edge::Both => (true, true),
+    };
+
+    // 上升沿触发选择寄存器
+    exti.rtsr.modify(|r, w| unsafe {
+        w.bits(if rising {
+            r.bits() | mask
+        } else {
+            r.bits() & !mask
+        })
+    });
+    // 下降沿触发选择寄存器
+    exti.ftsr.modify(|r, w| unsafe {
+        w.bits(if falling {
+            r.bits() | mask
+        } else {
+            r.bits() & !mask
+        })
+    });
+}
+
+/// EXTI 线的中断是否挂起
+pub fn is_pending(line: u8) -> bool {
+    // 只读访问挂起寄存器
+    let exti = unsafe { &*EXTI::ptr() };
+    exti.pr.read().bits() & (1 << line) != 0
+}
+
+/// 清除 EXTI 线的挂起标志
+pub fn clear_pending(line: u8) {
+    // 挂起寄存器写 1 清除，写 0 无效，不会影响其它线
+    let exti = unsafe { &*EXTI::ptr() };
+    exti.pr.write(|w| unsafe { w.bits(1 << line) });
+}
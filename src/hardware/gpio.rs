@@ -57,7 +57,107 @@
 //! }
 //! ```
 
-use stm32f1::stm32f103::{GPIOA, GPIOB};
+use stm32f1::stm32f103::{gpioa, GPIOA, GPIOB, GPIOC, GPIOD, GPIOE, RCC};
+
+/// GPIO 端口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Port {
+    A,
+    B,
+    C,
+    D,
+    E,
+}
+
+impl Port {
+    /// 端口序号，PA 为 0，PB 为 1，以此类推
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// 端口寄存器组
+    /// 所有端口的寄存器布局相同
+    pub fn regs(self) -> &'static gpioa::RegisterBlock {
+        let ptr = match self {
+            Port::A => GPIOA::ptr(),
+            Port::B => GPIOB::ptr(),
+            Port::C => GPIOC::ptr(),
+            Port::D => GPIOD::ptr(),
+            Port::E => GPIOE::ptr(),
+        };
+        unsafe { &*ptr }
+    }
+
+    /// 使能 APB2 上的端口时钟
+    pub fn enable_clock(self, rcc: &RCC) {
+        // IOPAEN 为第 2 位，之后依次为 IOPBEN、IOPCEN ...
+        let mask = 1 << (2 + self.index());
+        rcc.apb2enr
+            .modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+    }
+}
+
+/// 引脚模式
+/// 输出模式的速度均为 50MHz
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinMode {
+    /// 模拟输入
+    Analog,
+    /// 浮空输入
+    FloatingInput,
+    /// 上拉输入
+    PullUpInput,
+    /// 下拉输入
+    PullDownInput,
+    /// 推挽输出
+    PushPullOutput,
+    /// 开漏输出
+    OpenDrainOutput,
+    /// 复用推挽输出
+    AltPushPull,
+    /// 复用开漏输出
+    AltOpenDrain,
+}
+
+impl PinMode {
+    /// CRL/CRH 寄存器中每个引脚 4 位的配置值：CNF[1:0] MODE[1:0]
+    fn bits(self) -> u32 {
+        match self {
+            PinMode::Analog => 0b0000,
+            PinMode::FloatingInput => 0b0100,
+            PinMode::PullUpInput | PinMode::PullDownInput => 0b1000,
+            PinMode::PushPullOutput => 0b0011,
+            PinMode::OpenDrainOutput => 0b0111,
+            PinMode::AltPushPull => 0b1011,
+            PinMode::AltOpenDrain => 0b1111,
+        }
+    }
+}
+
+/// 设置引脚模式
+/// 需要先使能端口时钟
+pub fn set_pin_mode(port: Port, pin: u8, mode: PinMode) {
+    let gpio = port.regs();
+    // 每个引脚占 4 位，0~7 在 CRL，8~15 在 CRH
+    let shift = (pin as u32 % 8) * 4;
+    let mask = 0b1111 << shift;
+    let value = mode.bits() << shift;
+
+    if pin < 8 {
+        gpio.crl
+            .modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) });
+    } else {
+        gpio.crh
+            .modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) });
+    }
+
+    // 上拉/下拉由 ODR 决定
+    match mode {
+        PinMode::PullUpInput => gpio.bsrr.write(|w| unsafe { w.bits(1 << pin) }),
+        PinMode::PullDownInput => gpio.brr.write(|w| unsafe { w.bits(1 << pin) }),
+        _ => {}
+    }
+}
 
 pub struct Gpioa<'a> {
     gpio: &'a GPIOA,
@@ -67,6 +167,10 @@ impl<'a> Gpioa<'a> {
     pub fn new(gpioa: &'a GPIOA, pin: u16) -> Self {
         Gpioa { gpio: gpioa, pin }
     }
+    /// 引脚号
+    pub fn pin(&self) -> u16 {
+        self.pin
+    }
     /// 端口输出是否为低电平
     pub fn is_set_low(&self) -> bool {
         self.gpio.odr.read().bits() & (1 << self.pin) == 0
@@ -117,6 +221,10 @@ impl<'a> Gpiob<'a> {
     pub fn new(gpiob: &'a GPIOB, pin: u16) -> Self {
         Gpiob { gpio: gpiob, pin }
     }
+    /// 引脚号
+    pub fn pin(&self) -> u16 {
+        self.pin
+    }
     /// 端口输出是否为低电平
     pub fn is_set_low(&self) -> bool {
         self.gpio.odr.read().bits() & (1 << self.pin) == 0
@@ -0,0 +1,177 @@
+//! 独立看门狗（IWDG）
+//!
+//! 由 LSI（标称 40kHz，实际 30~60kHz）驱动的 12 位递减计数器，减到 0 时复位系统，
+//! 启动后不能停止，只能在计数到 0 之前喂狗：
+//! - KR 写 0x5555 解除 PR、RLR 的写保护
+//! - KR 写 0xAAAA 重新装载 RLR（喂狗）
+//! - KR 写 0xCCCC 启动，同时自动打开 LSI
+//!
+//! 超时时间 = 4 × 2^PR × (RLR + 1) / LSI，PR 为 0~6，RLR 为 0~0xFFF，
+//! 按 40kHz 计算范围约为 0.1ms~26.2s。由于 LSI 误差较大，喂狗间隔应留出足够余量。
+//!
+//! ```rust
+//! let mut watchdog = IndependentWatchdog::new(dp.IWDG);
+//! watchdog.stop_on_debug(&dp.DBGMCU, true);
+//! watchdog.start(1000).unwrap();
+//! loop {
+//!     watchdog.feed();
+//! }
+//! ```
+
+use stm32f1::stm32f103::{DBGMCU, IWDG, RCC};
+
+use super::rtc::LSI_HZ;
+
+/// 重新装载值的最大值
+pub const MAX_RELOAD: u16 = 0xFFF;
+/// 预分频 PR 的最大值，对应 256 分频
+const MAX_PRESCALER: u8 = 6;
+
+/// 看门狗错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 超时时间为 0
+    TimeoutTooShort,
+    /// 超时时间超过 256 分频时的最大值
+    TimeoutTooLong,
+}
+
+/// 由超时时间计算 (PR, RLR)
+/// 选择能表示该超时的最小分频，分辨率最高；结果向下取整，实际超时不长于 `timeout_ms`
+pub fn timeout_to_config(lsi_hz: u32, timeout_ms: u32) -> Result<(u8, u16), Error> {
+    for prescaler in 0..=MAX_PRESCALER {
+        let divider = 4u64 << prescaler;
+        let ticks = timeout_ms as u64 * lsi_hz as u64 / (1000 * divider);
+        if ticks <= MAX_RELOAD as u64 + 1 {
+            if ticks == 0 {
+                return Err(Error::TimeoutTooShort);
+            }
+            return Ok((prescaler, (ticks - 1) as u16));
+        }
+    }
+    Err(Error::TimeoutTooLong)
+}
+
+/// (PR, RLR) 对应的超时时间（微秒）
+pub fn config_to_timeout_us(lsi_hz: u32, prescaler: u8, reload: u16) -> u32 {
+    let divider = 4u64 << prescaler.min(MAX_PRESCALER);
+    ((reload as u64 + 1) * divider * 1_000_000 / lsi_hz as u64) as u32
+}
+
+/// 独立看门狗
+pub struct IndependentWatchdog {
+    iwdg: IWDG,
+    lsi_hz: u32,
+}
+
+impl IndependentWatchdog {
+    /// 按 LSI 标称频率计算超时
+    pub fn new(iwdg: IWDG) -> Self {
+        Self::with_lsi_frequency(iwdg, LSI_HZ)
+    }
+
+    /// 按实际测得的 LSI 频率计算超时
+    pub fn with_lsi_frequency(iwdg: IWDG, lsi_hz: u32) -> Self {
+        IndependentWatchdog { iwdg, lsi_hz }
+    }
+
+    /// 调试时（内核停止）是否冻结看门狗计数
+    /// 不冻结时，断点停留超过超时时间会复位
+    pub fn stop_on_debug(&self, dbgmcu: &DBGMCU, stop: bool) {
+        dbgmcu.cr.modify(|_, w| w.dbg_iwdg_stop().bit(stop));
+    }
+
+    /// 设置超时时间并启动看门狗，返回实际超时时间（微秒）
+    /// 已经启动时只修改超时时间
+    pub fn start(&mut self, timeout_ms: u32) -> Result<u32, Error> {
+        let (prescaler, reload) = timeout_to_config(self.lsi_hz, timeout_ms)?;
+        let iwdg = &self.iwdg;
+
+        // 启动后 LSI 才会打开，先启动再写 PR/RLR
+        iwdg.kr.write(|w| w.key().start());
+        // 解除写保护
+        iwdg.kr.write(|w| w.key().enable());
+        // 等待上一次更新完成
+        while iwdg.sr.read().pvu().bit_is_set() {}
+        iwdg.pr.write(|w| w.pr().bits(prescaler));
+        while iwdg.sr.read().rvu().bit_is_set() {}
+        iwdg.rlr.write(|w| w.rl().bits(reload));
+        // 等待新值写入 LSI 时钟域后再装载
+        while iwdg.sr.read().bits() != 0 {}
+        self.feed();
+
+        Ok(config_to_timeout_us(self.lsi_hz, prescaler, reload))
+    }
+
+    /// 喂狗，重新装载计数器
+    pub fn feed(&mut self) {
+        self.iwdg.kr.write(|w| w.key().reset());
+    }
+
+    /// 当前配置的超时时间（微秒）
+    pub fn timeout_us(&self) -> u32 {
+        let prescaler = self.iwdg.pr.read().pr().bits();
+        let reload = self.iwdg.rlr.read().rl().bits();
+        config_to_timeout_us(self.lsi_hz, prescaler, reload)
+    }
+}
+
+/// 上次复位是否由独立看门狗引起
+pub fn is_reset_cause(rcc: &RCC) -> bool {
+    rcc.csr.read().iwdgrstf().bit_is_set()
+}
+
+/// 清除 RCC_CSR 中的所有复位标志
+pub fn clear_reset_flags(rcc: &RCC) {
+    rcc.csr.modify(|_, w| w.rmvf().set_bit());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_boundaries() {
+        assert_eq!(timeout_to_config(LSI_HZ, 0), Err(Error::TimeoutTooShort));
+        // 最短 1ms：4 分频，10 个计数
+        assert_eq!(timeout_to_config(LSI_HZ, 1), Ok((0, 9)));
+        // 最长约 26.2s：256 分频，4096 个计数
+        assert_eq!(timeout_to_config(LSI_HZ, 26_214), Ok((6, 4094)));
+        assert_eq!(timeout_to_config(LSI_HZ, 26_220), Ok((6, MAX_RELOAD)));
+        assert_eq!(
+            timeout_to_config(LSI_HZ, 26_221),
+            Err(Error::TimeoutTooLong)
+        );
+        assert_eq!(
+            timeout_to_config(LSI_HZ, u32::MAX),
+            Err(Error::TimeoutTooLong)
+        );
+        assert_eq!(config_to_timeout_us(LSI_HZ, 0, 0), 100);
+        assert_eq!(config_to_timeout_us(LSI_HZ, 6, MAX_RELOAD), 26_214_400);
+    }
+
+    #[test]
+    fn prescaler_steps() {
+        // 每级分频的最大超时为 409.6ms × 2^PR
+        assert_eq!(timeout_to_config(LSI_HZ, 409), Ok((0, 4089)));
+        assert_eq!(timeout_to_config(LSI_HZ, 410), Ok((1, 2049)));
+        assert_eq!(timeout_to_config(LSI_HZ, 819), Ok((1, 4094)));
+        assert_eq!(timeout_to_config(LSI_HZ, 820), Ok((2, 2049)));
+        assert_eq!(timeout_to_config(LSI_HZ, 1638), Ok((2, 4094)));
+        assert_eq!(timeout_to_config(LSI_HZ, 1639), Ok((3, 2047)));
+        // 向下取整，4096.875 个计数仍然取 128 分频
+        assert_eq!(timeout_to_config(LSI_HZ, 13_110), Ok((5, MAX_RELOAD)));
+        assert_eq!(timeout_to_config(LSI_HZ, 13_111), Ok((6, 2047)));
+    }
+
+    #[test]
+    fn actual_timeout_not_longer() {
+        for timeout_ms in [1, 7, 100, 409, 410, 1000, 5000, 26_220] {
+            let (prescaler, reload) = timeout_to_config(LSI_HZ, timeout_ms).unwrap();
+            let actual_us = config_to_timeout_us(LSI_HZ, prescaler, reload);
+            let divider_us = (4 << prescaler) * 1_000_000 / LSI_HZ;
+            assert!(actual_us <= timeout_ms * 1000);
+            assert!(actual_us + divider_us > timeout_ms * 1000);
+        }
+    }
+}
@@ -1,5 +1,17 @@
 //!硬件外设
 pub mod acr;
+pub mod bkp;
 pub mod cfgr;
+pub mod delay;
+pub mod dma;
+pub mod exti;
 pub mod gpio;
+pub mod iwdg;
+pub mod nvic;
+pub mod power;
+pub mod pvd;
+pub mod rtc;
+pub mod serial;
 pub mod syst;
+pub mod timer;
+pub mod wwdg;
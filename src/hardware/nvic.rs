@@ -0,0 +1,228 @@
+//! 嵌套向量中断控制器（NVIC）优先级
+//!
+//! STM32F1 的优先级寄存器只实现了高 4 位，低 4 位读出恒为 0，
+//! 所以 `nvic.set_priority(irq, 0x1)` 实际写入的优先级为 0。
+//! 高 4 位再由 SCB AIRCR 寄存器的 PRIGROUP 字段划分为抢占优先级和子优先级：
+//!
+//! | 分组     | PRIGROUP | 抢占优先级 | 子优先级 |
+//! |----------|----------|------------|----------|
+//! | `Group0` | 0b111    | 0 位       | 4 位     |
+//! | `Group1` | 0b110    | 1 位       | 3 位     |
+//! | `Group2` | 0b101    | 2 位       | 2 位     |
+//! | `Group3` | 0b100    | 3 位       | 1 位     |
+//! | `Group4` | 0b011    | 4 位       | 0 位     |
+//!
+//! 数值越小优先级越高，只有抢占优先级更高的中断才能打断正在执行的中断。
+//!
+//! ```rust
+//! set_priority_grouping(&mut cp.SCB, PriorityGrouping::Group2);
+//! // 抢占优先级 1，子优先级 0
+//! set_priority(&mut cp.NVIC, Interrupt::TIM2, 1, 0).unwrap();
+//! ```
+
+use cortex_m::interrupt::InterruptNumber;
+use cortex_m::peripheral::{scb::SystemHandler, NVIC, SCB};
+
+/// 实现的优先级位数
+pub const PRIORITY_BITS: u8 = 4;
+
+/// AIRCR 寄存器写入密钥
+const AIRCR_VECTKEY: u32 = 0x05FA << 16;
+/// AIRCR 寄存器 PRIGROUP 字段偏移
+const AIRCR_PRIGROUP_SHIFT: u32 = 8;
+
+/// 优先级分组
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityGrouping {
+    /// 0 位抢占优先级，4 位子优先级
+    Group0,
+    /// 1 位抢占优先级，3 位子优先级
+    Group1,
+    /// 2 位抢占优先级，2 位子优先级
+    Group2,
+    /// 3 位抢占优先级，1 位子优先级
+    Group3,
+    /// 4 位抢占优先级，0 位子优先级
+    Group4,
+}
+
+impl PriorityGrouping {
+    /// 抢占优先级位数
+    pub const fn preempt_bits(self) -> u8 {
+        match self {
+            PriorityGrouping::Group0 => 0,
+            PriorityGrouping::Group1 => 1,
+            PriorityGrouping::Group2 => 2,
+            PriorityGrouping::Group3 => 3,
+            PriorityGrouping::Group4 => 4,
+        }
+    }
+
+    /// 子优先级位数
+    pub const fn sub_bits(self) -> u8 {
+        PRIORITY_BITS - self.preempt_bits()
+    }
+
+    /// AIRCR 寄存器 PRIGROUP 字段的值
+    pub const fn prigroup(self) -> u8 {
+        7 - self.preempt_bits()
+    }
+
+    /// 由 PRIGROUP 字段的值得到分组
+    /// PRIGROUP 小于 3 时 4 个实现位全部用作抢占优先级，与 `Group4` 相同
+    pub const fn from_prigroup(prigroup: u8) -> Self {
+        match prigroup & 0b111 {
+            7 => PriorityGrouping::Group0,
+            6 => PriorityGrouping::Group1,
+            5 => PriorityGrouping::Group2,
+            4 => PriorityGrouping::Group3,
+            _ => PriorityGrouping::Group4,
+        }
+    }
+}
+
+/// 优先级错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 抢占优先级超出当前分组的范围
+    PreemptOutOfRange,
+    /// 子优先级超出当前分组的范围
+    SubOutOfRange,
+}
+
+/// 将抢占优先级和子优先级编码为优先级寄存器的值
+pub fn encode_priority(grouping: PriorityGrouping, preempt: u8, sub: u8) -> Result<u8, Error> {
+    let sub_bits = grouping.sub_bits();
+    if preempt >= 1 << grouping.preempt_bits() {
+        return Err(Error::PreemptOutOfRange);
+    }
+    if sub >= 1 << sub_bits {
+        return Err(Error::SubOutOfRange);
+    }
+
+    // 逻辑优先级放在寄存器的高 4 位
+    Ok(((preempt << sub_bits) | sub) << (8 - PRIORITY_BITS))
+}
+
+/// 将优先级寄存器的值解码为 (抢占优先级, 子优先级)
+pub fn decode_priority(grouping: PriorityGrouping, priority: u8) -> (u8, u8) {
+    let value = priority >> (8 - PRIORITY_BITS);
+    let sub_bits = grouping.sub_bits();
+    (value >> sub_bits, value & ((1 << sub_bits) - 1))
+}
+
+/// 设置优先级分组
+/// 应在使能任何中断之前设置一次
+pub fn set_priority_grouping(scb: &mut SCB, grouping: PriorityGrouping) {
+    let value = AIRCR_VECTKEY | (grouping.prigroup() as u32) << AIRCR_PRIGROUP_SHIFT;
+    unsafe { scb.aircr.write(value) };
+}
+
+/// 读取当前的优先级分组
+pub fn priority_grouping() -> PriorityGrouping {
+    // 只读访问 AIRCR 寄存器
+    let aircr = unsafe { (*SCB::PTR).aircr.read() };
+    PriorityGrouping::from_prigroup((aircr >> AIRCR_PRIGROUP_SHIFT) as u8)
+}
+
+/// 按当前的优先级分组设置中断的抢占优先级和子优先级
+pub fn set_priority<I: InterruptNumber>(
+    nvic: &mut NVIC,
+    irq: I,
+    preempt: u8,
+    sub: u8,
+) -> Result<(), Error> {
+    let priority = encode_priority(priority_grouping(), preempt, sub)?;
+    unsafe { nvic.set_priority(irq, priority) };
+    Ok(())
+}
+
+/// 读取中断的 (抢占优先级, 子优先级)
+pub fn get_priority<I: InterruptNumber>(irq: I) -> (u8, u8) {
+    decode_priority(priority_grouping(), NVIC::get_priority(irq))
+}
+
+/// 按当前的优先级分组设置系统异常（如 SysTick）的抢占优先级和子优先级
+pub fn set_system_handler_priority(
+    scb: &mut SCB,
+    handler: SystemHandler,
+    preempt: u8,
+    sub: u8,
+) -> Result<(), Error> {
+    let priority = encode_priority(priority_grouping(), preempt, sub)?;
+    unsafe { scb.set_priority(handler, priority) };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUPS: [PriorityGrouping; 5] = [
+        PriorityGrouping::Group0,
+        PriorityGrouping::Group1,
+        PriorityGrouping::Group2,
+        PriorityGrouping::Group3,
+        PriorityGrouping::Group4,
+    ];
+
+    #[test]
+    fn prigroup_round_trip() {
+        for grouping in GROUPS {
+            assert_eq!(
+                PriorityGrouping::from_prigroup(grouping.prigroup()),
+                grouping
+            );
+            assert_eq!(grouping.preempt_bits() + grouping.sub_bits(), PRIORITY_BITS);
+        }
+        assert_eq!(PriorityGrouping::Group0.prigroup(), 7);
+        assert_eq!(PriorityGrouping::Group4.prigroup(), 3);
+        // PRIGROUP 小于 3 时全部为抢占优先级
+        assert_eq!(PriorityGrouping::from_prigroup(0), PriorityGrouping::Group4);
+    }
+
+    #[test]
+    fn encode_uses_upper_nibble() {
+        assert_eq!(encode_priority(PriorityGrouping::Group4, 0, 0), Ok(0x00));
+        assert_eq!(encode_priority(PriorityGrouping::Group4, 1, 0), Ok(0x10));
+        assert_eq!(encode_priority(PriorityGrouping::Group4, 15, 0), Ok(0xF0));
+        assert_eq!(encode_priority(PriorityGrouping::Group0, 0, 15), Ok(0xF0));
+        assert_eq!(encode_priority(PriorityGrouping::Group2, 1, 0), Ok(0x40));
+        assert_eq!(encode_priority(PriorityGrouping::Group2, 2, 3), Ok(0xB0));
+        assert_eq!(encode_priority(PriorityGrouping::Group1, 1, 5), Ok(0xD0));
+        assert_eq!(encode_priority(PriorityGrouping::Group3, 7, 1), Ok(0xF0));
+    }
+
+    #[test]
+    fn encode_rejects_out_of_range() {
+        assert_eq!(
+            encode_priority(PriorityGrouping::Group0, 1, 0),
+            Err(Error::PreemptOutOfRange)
+        );
+        assert_eq!(
+            encode_priority(PriorityGrouping::Group4, 0, 1),
+            Err(Error::SubOutOfRange)
+        );
+        assert_eq!(
+            encode_priority(PriorityGrouping::Group2, 4, 0),
+            Err(Error::PreemptOutOfRange)
+        );
+        assert_eq!(
+            encode_priority(PriorityGrouping::Group2, 0, 4),
+            Err(Error::SubOutOfRange)
+        );
+    }
+
+    #[test]
+    fn decode_round_trip() {
+        for grouping in GROUPS {
+            for preempt in 0..(1 << grouping.preempt_bits()) {
+                for sub in 0..(1 << grouping.sub_bits()) {
+                    let priority = encode_priority(grouping, preempt, sub).unwrap();
+                    assert_eq!(priority & 0x0F, 0);
+                    assert_eq!(decode_priority(grouping, priority), (preempt, sub));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! 低功耗模式
+//!
+//! | 模式    | 进入方式                          | 唤醒                                   | 唤醒后               |
+//! |---------|-----------------------------------|----------------------------------------|----------------------|
+//! | 睡眠    | WFI/WFE，SLEEPDEEP = 0            | 任意中断（WFI）/ 事件（WFE）           | 从下一条指令继续     |
+//! | 停止    | SLEEPDEEP = 1，PDDS = 0           | EXTI 线（含 PVD、RTC 闹钟）中断或事件  | 时钟为 HSI，需重新配置 |
+//! | 待机    | SLEEPDEEP = 1，PDDS = 1           | WKUP 引脚上升沿、RTC 闹钟、NRST、IWDG  | 复位，SBF 置位       |
+//!
+//! 停止模式中 1.8V 域时钟全部停止，SRAM 和寄存器内容保持，稳压器可以切换到低功耗模式进一步降低功耗，
+//! 代价是唤醒时间更长。待机模式中 SRAM 和寄存器内容丢失，只有备份域保持。
+//!
+//! ```rust
+//! // 按键（PB1）下降沿唤醒停止模式
+//! power::enable_wakeup_line(exti, 1, Edge::Falling, WakeMode::Interrupt);
+//! power::stop(&mut cp.SCB, pwr, rcc, Regulator::LowPower);
+//!
+//! // WKUP 引脚（PA0）上升沿唤醒待机模式
+//! power::enable_wakeup_pin(pwr);
+//! power::standby(&mut cp.SCB, pwr);
+//! ```
+
+use cortex_m::asm;
+use cortex_m::peripheral::SCB;
+use stm32f1::stm32f103::{EXTI, PWR, RCC};
+
+use super::cfgr::set_clock;
+use super::exti::{self, Edge};
+
+/// 停止模式中的稳压器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Regulator {
+    /// 稳压器保持开启，唤醒较快
+    On,
+    /// 稳压器处于低功耗模式，功耗更低
+    LowPower,
+}
+
+/// 等待指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitFor {
+    /// WFI，由中断唤醒
+    Interrupt,
+    /// WFE，由事件（或 SEVONPEND 置位时挂起的中断）唤醒
+    Event,
+}
+
+/// EXTI 线的唤醒方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeMode {
+    /// 中断，配合 WFI，需要在 NVIC 中使能对应中断
+    Interrupt,
+    /// 事件，配合 WFE，不进入中断处理函数
+    Event,
+}
+
+/// 复位后的唤醒原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeReason {
+    /// 上电或普通复位，没有进入过待机模式
+    Reset,
+    /// 由唤醒事件（WKUP 引脚上升沿、RTC 闹钟）从待机模式唤醒
+    StandbyWakeup,
+    /// 由 NRST 引脚或独立看门狗复位从待机模式唤醒
+    StandbyReset,
+}
+
+/// 睡眠模式
+/// 内核停止，外设继续运行，任意中断（WFI）或事件（WFE）唤醒
+pub fn sleep(scb: &mut SCB, wait: WaitFor) {
+    scb.clear_sleepdeep();
+    wait_for(wait);
+}
+
+/// 设置退出中断后自动进入睡眠
+/// 置位后主程序在中断返回时不再继续执行，适合完全由中断驱动的程序
+pub fn set_sleep_on_exit(scb: &mut SCB, enable: bool) {
+    if enable {
+        scb.set_sleeponexit();
+    } else {
+        scb.clear_sleeponexit();
+    }
+}
+
+/// 停止模式
+/// 唤醒后系统时钟为 HSI，这里重新调用 `set_clock` 恢复 72MHz 并更新延时使用的时钟频率
+pub fn stop(scb: &mut SCB, pwr: &PWR, rcc: &RCC, regulator: Regulator) {
+    stop_with(scb, pwr, regulator, WaitFor::Interrupt);
+    set_clock(rcc);
+}
+
+/// 停止模式，唤醒后不恢复时钟
+/// 系统时钟保持为 HSI（8MHz），由调用者重新配置
+pub fn stop_with(scb: &mut SCB, pwr: &PWR, regulator: Regulator, wait: WaitFor) {
+    enable_pwr_clock();
+    pwr.cr.modify(|_, w| {
+        w.pdds()
+            .stop_mode()
+            .lpds()
+            .bit(regulator == Regulator::LowPower)
+    });
+
+    scb.set_sleepdeep();
+    wait_for(wait);
+    scb.clear_sleepdeep();
+}
+
+/// 待机模式
+/// 唤醒后从复位开始执行，可以用 `wake_reason` 判断
+pub fn standby(scb: &mut SCB, pwr: &PWR) -> ! {
+    enable_pwr_clock();
+    // 清除唤醒标志，否则会立即唤醒
+    pwr.cr
+        .modify(|_, w| w.pdds().standby_mode().cwuf().set_bit());
+
+    scb.set_sleepdeep();
+    loop {
+        asm::dsb();
+        asm::wfi();
+    }
+}
+
+/// 使能 WKUP 引脚（PA0）
+/// 使能后 PA0 被强制为下拉输入，上升沿从待机模式唤醒
+pub fn enable_wakeup_pin(pwr: &PWR) {
+    enable_pwr_clock();
+    pwr.csr.modify(|_, w| w.ewup().set_bit());
+}
+
+/// 关闭 WKUP 引脚
+pub fn disable_wakeup_pin(pwr: &PWR) {
+    pwr.csr.modify(|_, w| w.ewup().clear_bit());
+}
+
+/// 配置 EXTI 线唤醒停止模式
+/// GPIO 线需要先通过 AFIO 选择端口；RTC 闹钟为线 17 上升沿，PVD 为线 16
+pub fn enable_wakeup_line(exti: &EXTI, line: u8, edge: Edge, mode: WakeMode) {
+    let mask = 1 << line;
+    exti::set_trigger(exti, line, edge);
+    exti::clear_pending(line);
+    match mode {
+        WakeMode::Interrupt => exti.imr.modify(|r, w| unsafe { w.bits(r.bits() | mask) }),
+        WakeMode::Event => exti.emr.modify(|r, w| unsafe { w.bits(r.bits() | mask) }),
+    }
+}
+
+/// 取消 EXTI 线的唤醒
+pub fn disable_wakeup_line(exti: &EXTI, line: u8) {
+    let mask = !(1 << line);
+    exti.imr.modify(|r, w| unsafe { w.bits(r.bits() & mask) });
+    exti.emr.modify(|r, w| unsafe { w.bits(r.bits() & mask) });
+}
+
+/// 读取 PWR_CSR 判断唤醒原因
+pub fn wake_reason(pwr: &PWR) -> WakeReason {
+    enable_pwr_clock();
+    let csr = pwr.csr.read();
+    if csr.sbf().bit_is_clear() {
+        WakeReason::Reset
+    } else if csr.wuf().bit_is_set() {
+        WakeReason::StandbyWakeup
+    } else {
+        WakeReason::StandbyReset
+    }
+}
+
+/// 清除待机标志和唤醒标志
+pub fn clear_wake_flags(pwr: &PWR) {
+    pwr.cr.modify(|_, w| w.csbf().set_bit().cwuf().set_bit());
+}
+
+fn wait_for(wait: WaitFor) {
+    // 等待之前的存储器访问完成
+    asm::dsb();
+    match wait {
+        WaitFor::Interrupt => asm::wfi(),
+        WaitFor::Event => asm::wfe(),
+    }
+}
+
+/// 使能 PWR 时钟
+fn enable_pwr_clock() {
+    let rcc = unsafe { &*RCC::ptr() };
+    rcc.apb1enr.modify(|_, w| w.pwren().set_bit());
+}
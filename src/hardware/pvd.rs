@@ -0,0 +1,114 @@
+//! 可编程电压监测器（PVD）
+//!
+//! PVD 比较 VDD 与 PWR_CR 中 PLS 选择的阈值（2.2~2.9V，每级 0.1V，约 100mV 迟滞）：
+//! - VDD 低于阈值时 PWR_CSR 的 PVDO 置位
+//! - PVDO 连接到 EXTI 线 16，上升沿表示电压跌落，下降沿表示电压恢复
+//!
+//! 在 PVD 中断中可以赶在掉电复位之前保存数据。
+//!
+//! ```rust
+//! pvd::enable(pwr, PvdLevel::V2_9);
+//! pvd::listen(exti, SupplyEvent::Drop);
+//! unsafe { NVIC::unmask(Interrupt::PVD) };
+//!
+//! #[interrupt]
+//! fn PVD() {
+//!     pvd::clear_pending();
+//! }
+//! ```
+
+use stm32f1::stm32f103::{EXTI, PWR, RCC};
+
+use super::exti::{self, Edge};
+
+/// 连接 PVD 输出的 EXTI 线
+pub const PVD_EXTI_LINE: u8 = 16;
+
+/// PVD 阈值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PvdLevel {
+    V2_2,
+    V2_3,
+    V2_4,
+    V2_5,
+    V2_6,
+    V2_7,
+    V2_8,
+    V2_9,
+}
+
+impl PvdLevel {
+    /// PLS 字段的值
+    pub const fn bits(self) -> u8 {
+        self as u8
+    }
+
+    /// 阈值（毫伏），为电压下降时的阈值，上升时约高 100mV
+    pub const fn millivolts(self) -> u16 {
+        2200 + self as u16 * 100
+    }
+}
+
+/// 触发中断的电源事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupplyEvent {
+    /// VDD 跌落到阈值以下（PVDO 上升沿）
+    Drop,
+    /// VDD 恢复到阈值以上（PVDO 下降沿）
+    Recover,
+    /// 跌落和恢复
+    Both,
+}
+
+/// 设置阈值并使能 PVD
+pub fn enable(pwr: &PWR, level: PvdLevel) {
+    let rcc = unsafe { &*RCC::ptr() };
+    rcc.apb1enr.modify(|_, w| w.pwren().set_bit());
+    pwr.cr
+        .modify(|_, w| unsafe { w.pls().bits(level.bits()).pvde().set_bit() });
+}
+
+/// 关闭 PVD
+pub fn disable(pwr: &PWR) {
+    pwr.cr.modify(|_, w| w.pvde().clear_bit());
+}
+
+/// 设置阈值
+pub fn set_level(pwr: &PWR, level: PvdLevel) {
+    pwr.cr.modify(|_, w| unsafe { w.pls().bits(level.bits()) });
+}
+
+/// VDD 是否低于阈值（PVDO）
+/// 使能 PVD 后需要等待约 100us 输出才有效
+pub fn is_below_threshold(pwr: &PWR) -> bool {
+    pwr.csr.read().pvdo().bit_is_set()
+}
+
+/// 使能 PVD 中断（EXTI 线 16），还需要在 NVIC 中使能 `Interrupt::PVD`
+pub fn listen(exti: &EXTI, event: SupplyEvent) {
+    let edge = match event {
+        SupplyEvent::Drop => Edge::Rising,
+        SupplyEvent::Recover => Edge::Falling,
+        SupplyEvent::Both => Edge::Both,
+    };
+    exti::set_trigger(exti, PVD_EXTI_LINE, edge);
+    exti::clear_pending(PVD_EXTI_LINE);
+    exti.imr
+        .modify(|r, w| unsafe { w.bits(r.bits() | 1 << PVD_EXTI_LINE) });
+}
+
+/// 关闭 PVD 中断
+pub fn unlisten(exti: &EXTI) {
+    exti.imr
+        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << PVD_EXTI_LINE)) });
+}
+
+/// PVD 中断是否挂起
+pub fn is_pending() -> bool {
+    exti::is_pending(PVD_EXTI_LINE)
+}
+
+/// 清除 PVD 中断挂起标志
+pub fn clear_pending() {
+    exti::clear_pending(PVD_EXTI_LINE);
+}
@@ -0,0 +1,454 @@
+//! 实时时钟（RTC）
+//!
+//! RTC 位于备份域，由 VBAT 供电时在复位和待机模式下继续计数：
+//! - 时钟源：LSE（32.768kHz）、LSI（约 40kHz）、HSE/128
+//! - 20 位预分频器 PRL 把 RTCCLK 分频为 1Hz 的秒信号 TR_CLK，PRL = 频率 - 1
+//! - 32 位计数器 CNT 每秒加 1，与闹钟寄存器 ALR 相等时产生闹钟事件
+//!
+//! 配置顺序：
+//! 1. 使能 PWR 和 BKP 时钟，置位 PWR_CR 的 DBP 解除备份域写保护
+//! 2. 写 CRL/CRH 前等待 RTOFF = 1
+//! 3. 置位 CNF 进入配置模式，写 PRL、CNT、ALR
+//! 4. 清除 CNF 退出配置模式，等待 RTOFF = 1，写操作完成
+//!
+//! 复位或 APB1 时钟停止后，需要等待 RSF 置位才能读取到正确的寄存器值。
+//! 闹钟中断经过 EXTI 线 17（上升沿）送到 RTCALARM 中断，也可以把 MCU 从停止模式唤醒。
+//!
+//! ```rust
+//! let mut rtc = Rtc::new(dp.RTC, rcc, pwr, RtcClockSource::Lse);
+//! rtc.set_time(0);
+//! rtc.set_alarm(10);
+//! rtc.listen_alarm(exti);
+//! ```
+
+use stm32f1::stm32f103::{EXTI, PWR, RCC, RTC};
+
+use super::cfgr::HSE_HZ;
+use super::exti::{self, Edge};
+use crate::calendar::{self, DateTime, Weekday};
+
+/// LSE 频率
+pub const LSE_HZ: u32 = 32_768;
+/// LSI 标称频率，实际在 30~60kHz 之间
+pub const LSI_HZ: u32 = 40_000;
+/// 连接 RTC 闹钟的 EXTI 线
+pub const ALARM_EXTI_LINE: u8 = 17;
+
+/// 校准周期：每 2^20 个 RTCCLK 脉冲中去掉 CAL 个
+pub const CALIBRATION_PERIOD: u32 = 1 << 20;
+/// CAL 的最大值
+pub const MAX_CALIBRATION: u8 = 0x7F;
+
+/// CRL 中写 0 清除的标志：SECF、ALRF、OWF、RSF
+const CRL_FLAGS: u32 = 0b1111;
+/// CRL 的 CNF 位
+const CRL_CNF: u32 = 1 << 4;
+
+/// RTC 时钟源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtcClockSource {
+    /// 外部低速晶振
+    Lse,
+    /// 内部低速 RC 振荡器，掉电后停止，精度较低
+    Lsi,
+    /// 外部高速晶振 128 分频，掉电后停止
+    HseDiv128,
+}
+
+impl RtcClockSource {
+    /// RTCCLK 频率
+    pub const fn frequency(self) -> u32 {
+        match self {
+            RtcClockSource::Lse => LSE_HZ,
+            RtcClockSource::Lsi => LSI_HZ,
+            RtcClockSource::HseDiv128 => HSE_HZ / 128,
+        }
+    }
+}
+
+/// RTC 事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// 秒事件
+    Second,
+    /// 闹钟事件
+    Alarm,
+    /// 计数器溢出
+    Overflow,
+}
+
+impl Event {
+    /// CRL 中的标志位，CRH 中的中断使能位与之相同
+    const fn mask(self) -> u32 {
+        match self {
+            Event::Second => 1 << 0,
+            Event::Alarm => 1 << 1,
+            Event::Overflow => 1 << 2,
+        }
+    }
+}
+
+/// 校准错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationError {
+    /// 时钟偏慢，数字校准只能去掉脉冲使时钟变慢，需要调整预分频值
+    ClockSlow,
+    /// 偏快超过约 121ppm，超出 CAL 的范围
+    OutOfRange,
+}
+
+/// 由时钟偏快的 ppm 计算 BKP_RTCCR 的 CAL 值
+/// 每个校准步长约为 0.954ppm（1 / 2^20），结果四舍五入；`ppm` 为负表示时钟偏慢，
+/// 偏慢不足半个步长时四舍五入为 0，不需要校准
+pub fn ppm_to_calibration(ppm: f32) -> Result<u8, CalibrationError> {
+    let steps = ppm * CALIBRATION_PERIOD as f32 / 1_000_000.0;
+    // 四舍五入后步数为负
+    if steps <= -0.5 {
+        return Err(CalibrationError::ClockSlow);
+    }
+    let steps = steps.max(0.0) + 0.5;
+    if steps >= MAX_CALIBRATION as f32 + 1.0 {
+        return Err(CalibrationError::OutOfRange);
+    }
+    Ok(steps as u8)
+}
+
+/// CAL 值对应的校准量（ppm）
+pub fn calibration_to_ppm(calibration: u8) -> f32 {
+    (calibration & MAX_CALIBRATION) as f32 * 1_000_000.0 / CALIBRATION_PERIOD as f32
+}
+
+/// 由测量频率和标称频率计算误差（ppm），偏快为正
+/// 例如测量校准输出（RTCCLK/64，LSE 时标称 512Hz）的频率
+pub fn frequency_error_ppm(measured_hz: f32, nominal_hz: f32) -> f32 {
+    (measured_hz - nominal_hz) / nominal_hz * 1_000_000.0
+}
+
+/// 由一段时间内的走时误差计算误差（ppm），`gained_seconds` 为快了的秒数（慢为负）
+pub fn drift_ppm(gained_seconds: f32, elapsed_seconds: f32) -> f32 {
+    gained_seconds / elapsed_seconds * 1_000_000.0
+}
+
+/// 实时时钟
+pub struct Rtc {
+    rtc: RTC,
+    source: RtcClockSource,
+}
+
+impl Rtc {
+    /// 初始化 RTC
+    ///
+    /// 备份域中的 RTC 已经以相同的时钟源运行时只恢复访问，计数值保持不变；
+    /// 时钟源不同时复位备份域后重新配置，备份数据寄存器会被清除。
+    pub fn new(rtc: RTC, rcc: &RCC, pwr: &PWR, source: RtcClockSource) -> Self {
+        enable_backup_domain(rcc, pwr);
+
+        let bdcr = rcc.bdcr.read();
+        let selected = match source {
+            RtcClockSource::Lse => bdcr.rtcsel().is_lse(),
+            RtcClockSource::Lsi => bdcr.rtcsel().is_lsi(),
+            RtcClockSource::HseDiv128 => bdcr.rtcsel().is_hse(),
+        };
+        let configured = bdcr.rtcen().bit_is_set() && selected;
+
+        if !configured {
+            if bdcr.rtcen().bit_is_set() || !bdcr.rtcsel().is_no_clock() {
+                // RTCSEL 只能通过复位备份域修改
+                rcc.bdcr.modify(|_, w| w.bdrst().set_bit());
+                rcc.bdcr.modify(|_, w| w.bdrst().clear_bit());
+            }
+            enable_oscillator(rcc, source);
+            rcc.bdcr.modify(|_, w| {
+                match source {
+                    RtcClockSource::Lse => w.rtcsel().lse(),
+                    RtcClockSource::Lsi => w.rtcsel().lsi(),
+                    RtcClockSource::HseDiv128 => w.rtcsel().hse(),
+                };
+                w.rtcen().enabled()
+            });
+        } else if source != RtcClockSource::Lse {
+            // 只有 LSE 在备份域中，LSI 和 HSE 在复位后都会关闭，需要重新打开
+            enable_oscillator(rcc, source);
+        }
+
+        let mut rtc = Rtc { rtc, source };
+        rtc.wait_for_sync();
+        if !configured {
+            rtc.set_prescaler(source.frequency() - 1);
+        }
+        rtc
+    }
+
+    /// 释放 RTC 外设，RTC 继续运行
+    pub fn release(self) -> RTC {
+        self.rtc
+    }
+
+    /// 时钟源
+    pub fn source(&self) -> RtcClockSource {
+        self.source
+    }
+
+    /// 等待寄存器同步
+    /// 复位或从停止模式唤醒后，读取寄存器之前需要调用
+    pub fn wait_for_sync(&mut self) {
+        let rtc = &self.rtc;
+        self.write_crl(|crl| crl & !(1 << 3));
+        while rtc.crl.read().rsf().bit_is_clear() {}
+    }
+
+    /// 设置预分频值（20 位），TR_CLK = RTCCLK / (prescaler + 1)
+    pub fn set_prescaler(&mut self, prescaler: u32) {
+        self.configure(|rtc| {
+            rtc.prlh
+                .write(|w| w.prlh().bits(((prescaler >> 16) & 0xF) as u8));
+            rtc.prll.write(|w| w.prll().bits(prescaler as u16));
+        });
+    }
+
+    /// 设置计数值（秒）
+    pub fn set_time(&mut self, seconds: u32) {
+        self.configure(|rtc| {
+            rtc.cnth.write(|w| w.cnth().bits((seconds >> 16) as u16));
+            rtc.cntl.write(|w| w.cntl().bits(seconds as u16));
+        });
+    }
+
+    /// 当前计数值（秒）
+    /// 高 16 位读两次，两次不同说明读取期间低 16 位进位，重新读取
+    pub fn current_time(&self) -> u32 {
+        let rtc = &self.rtc;
+        loop {
+            let high = rtc.cnth.read().cnth().bits();
+            let low = rtc.cntl.read().cntl().bits();
+            if rtc.cnth.read().cnth().bits() == high {
+                return (high as u32) << 16 | low as u32;
+            }
+        }
+    }
+
+    /// 设置闹钟，计数值等于 `seconds` 时产生闹钟事件
+    pub fn set_alarm(&mut self, seconds: u32) {
+        self.configure(|rtc| {
+            rtc.alrh.write(|w| w.alrh().bits((seconds >> 16) as u16));
+            rtc.alrl.write(|w| w.alrl().bits(seconds as u16));
+        });
+    }
+
+    /// 设置闹钟为 `seconds` 秒之后
+    pub fn set_alarm_after(&mut self, seconds: u32) {
+        let alarm = self.current_time().wrapping_add(seconds);
+        self.set_alarm(alarm);
+    }
+
+    /// 按日期时间设置计数值，计数值为 Unix 时间戳
+    pub fn set_datetime(&mut self, datetime: &DateTime) -> Result<(), calendar::Error> {
+        let timestamp = datetime.timestamp()?;
+        self.set_time(timestamp);
+        Ok(())
+    }
+
+    /// 当前日期时间
+    pub fn datetime(&self) -> DateTime {
+        DateTime::from_timestamp(self.current_time())
+    }
+
+    /// 设置闹钟为下一个每天 `hour:minute:second`，返回闹钟的时间戳
+    /// 闹钟触发后再次调用即可设置第二天的闹钟
+    pub fn set_alarm_daily(&mut self, hour: u8, minute: u8, second: u8) -> Option<u32> {
+        let alarm = calendar::next_daily(self.current_time(), hour, minute, second)?;
+        self.set_alarm(alarm);
+        Some(alarm)
+    }
+
+    /// 设置闹钟为下一个每周 `weekday` 的 `hour:minute:second`，返回闹钟的时间戳
+    pub fn set_alarm_weekly(
+        &mut self,
+        weekday: Weekday,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Option<u32> {
+        let alarm = calendar::next_weekly(self.current_time(), weekday, hour, minute, second)?;
+        self.set_alarm(alarm);
+        Some(alarm)
+    }
+
+    /// 使能秒中断（RTC 中断）
+    pub fn listen_seconds(&mut self) {
+        self.listen(Event::Second);
+    }
+
+    /// 关闭秒中断
+    pub fn unlisten_seconds(&mut self) {
+        self.unlisten(Event::Second);
+    }
+
+    /// 使能闹钟中断（RTCALARM 中断）
+    /// 同时配置 EXTI 线 17 为上升沿触发并取消屏蔽
+    pub fn listen_alarm(&mut self, exti: &EXTI) {
+        exti::set_trigger(exti, ALARM_EXTI_LINE, Edge::Rising);
+        exti::clear_pending(ALARM_EXTI_LINE);
+        exti.imr
+            .modify(|r, w| unsafe { w.bits(r.bits() | 1 << ALARM_EXTI_LINE) });
+        self.listen(Event::Alarm);
+    }
+
+    /// 关闭闹钟中断
+    pub fn unlisten_alarm(&mut self, exti: &EXTI) {
+        self.unlisten(Event::Alarm);
+        exti.imr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << ALARM_EXTI_LINE)) });
+    }
+
+    /// 使能事件中断
+    pub fn listen(&mut self, event: Event) {
+        self.clear_flag(event);
+        self.write_crh(|crh| crh | event.mask());
+    }
+
+    /// 关闭事件中断
+    pub fn unlisten(&mut self, event: Event) {
+        self.write_crh(|crh| crh & !event.mask());
+    }
+
+    /// 事件标志是否置位
+    pub fn is_flag_set(&self, event: Event) -> bool {
+        self.rtc.crl.read().bits() & event.mask() != 0
+    }
+
+    /// 清除事件标志
+    /// 闹钟事件同时清除 EXTI 线 17 的挂起标志
+    pub fn clear_flag(&mut self, event: Event) {
+        let mask = event.mask();
+        // 其它标志写 1 不受影响
+        self.write_crl(|crl| crl & !mask);
+        if event == Event::Alarm {
+            exti::clear_pending(ALARM_EXTI_LINE);
+        }
+    }
+
+    /// 清除秒标志
+    pub fn clear_second_flag(&mut self) {
+        self.clear_flag(Event::Second);
+    }
+
+    /// 清除闹钟标志
+    pub fn clear_alarm_flag(&mut self) {
+        self.clear_flag(Event::Alarm);
+    }
+
+    /// 清除溢出标志
+    pub fn clear_overflow_flag(&mut self) {
+        self.clear_flag(Event::Overflow);
+    }
+
+    /// 在配置模式中写入 PRL、CNT、ALR
+    fn configure(&mut self, f: impl FnOnce(&RTC)) {
+        let rtc = &self.rtc;
+        self.write_crl(|crl| crl | CRL_CNF);
+        f(rtc);
+        self.write_crl(|crl| crl & !CRL_CNF);
+        // 退出配置模式后写操作才开始，等待完成
+        wait_write_done(rtc);
+    }
+
+    /// 写 CRL
+    /// `f` 的参数中标志位均为 1，需要清除的标志位置 0
+    fn write_crl(&self, f: impl FnOnce(u32) -> u32) {
+        let rtc = &self.rtc;
+        wait_write_done(rtc);
+        let cnf = rtc.crl.read().bits() & CRL_CNF;
+        rtc.crl.write(|w| unsafe { w.bits(f(cnf | CRL_FLAGS)) });
+    }
+
+    /// 写 CRH
+    fn write_crh(&self, f: impl FnOnce(u32) -> u32) {
+        let rtc = &self.rtc;
+        wait_write_done(rtc);
+        rtc.crh.modify(|r, w| unsafe { w.bits(f(r.bits())) });
+    }
+}
+
+/// 使能 PWR 和 BKP 时钟，解除备份域写保护
+pub fn enable_backup_domain(rcc: &RCC, pwr: &PWR) {
+    rcc.apb1enr
+        .modify(|_, w| w.pwren().set_bit().bkpen().set_bit());
+    pwr.cr.modify(|_, w| w.dbp().set_bit());
+}
+
+/// 打开时钟源并等待就绪
+fn enable_oscillator(rcc: &RCC, source: RtcClockSource) {
+    match source {
+        RtcClockSource::Lse => {
+            rcc.bdcr.modify(|_, w| w.lseon().set_bit());
+            while rcc.bdcr.read().lserdy().bit_is_clear() {}
+        }
+        RtcClockSource::Lsi => {
+            rcc.csr.modify(|_, w| w.lsion().set_bit());
+            while rcc.csr.read().lsirdy().bit_is_clear() {}
+        }
+        RtcClockSource::HseDiv128 => {
+            rcc.cr.modify(|_, w| w.hseon().set_bit());
+            while rcc.cr.read().hserdy().bit_is_clear() {}
+        }
+    }
+}
+
+/// 等待上一次写操作完成
+fn wait_write_done(rtc: &RTC) {
+    while rtc.crl.read().rtoff().bit_is_clear() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ppm_rounds_to_nearest_step() {
+        assert_eq!(ppm_to_calibration(0.0), Ok(0));
+        assert_eq!(ppm_to_calibration(0.4), Ok(0));
+        assert_eq!(ppm_to_calibration(0.5), Ok(1));
+        assert_eq!(ppm_to_calibration(0.954), Ok(1));
+        assert_eq!(ppm_to_calibration(10.0), Ok(10));
+        assert_eq!(ppm_to_calibration(20.0), Ok(21));
+        assert_eq!(ppm_to_calibration(100.0), Ok(105));
+        assert_eq!(ppm_to_calibration(121.0), Ok(127));
+    }
+
+    #[test]
+    fn ppm_out_of_range() {
+        assert_eq!(ppm_to_calibration(-1.0), Err(CalibrationError::ClockSlow));
+        assert_eq!(ppm_to_calibration(-0.5), Err(CalibrationError::ClockSlow));
+        // 偏慢不足半个步长，四舍五入后不需要校准
+        assert_eq!(ppm_to_calibration(-0.3), Ok(0));
+        assert_eq!(ppm_to_calibration(-0.0), Ok(0));
+        assert_eq!(ppm_to_calibration(121.6), Err(CalibrationError::OutOfRange));
+        assert_eq!(
+            ppm_to_calibration(1000.0),
+            Err(CalibrationError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn calibration_round_trip() {
+        for calibration in 0..=MAX_CALIBRATION {
+            let ppm = calibration_to_ppm(calibration);
+            assert_eq!(ppm_to_calibration(ppm), Ok(calibration));
+        }
+        assert!((calibration_to_ppm(MAX_CALIBRATION) - 121.116).abs() < 0.001);
+    }
+
+    #[test]
+    fn measured_error() {
+        // 512Hz 的校准输出测得 512.02Hz，偏快约 39.1ppm
+        let ppm = frequency_error_ppm(512.02, 512.0);
+        assert!((ppm - 39.06).abs() < 0.1);
+        assert_eq!(ppm_to_calibration(ppm), Ok(41));
+        // 每天快 2 秒约为 23.1ppm
+        let ppm = drift_ppm(2.0, 86_400.0);
+        assert!((ppm - 23.148).abs() < 0.001);
+        assert_eq!(ppm_to_calibration(ppm), Ok(24));
+        assert!(drift_ppm(-1.0, 86_400.0) < 0.0);
+    }
+}
@@ -0,0 +1,585 @@
+//! 中断驱动的缓冲串口
+//!
+//! 接收中断把收到的字节放入接收环形缓冲区，发送时把数据放入发送环形缓冲区并使能 TXE 中断，
+//! 由中断逐个写入 DR，缓冲区发送完后关闭 TXE 中断。
+//! 缓冲区放在 `static` 中，主程序和中断处理函数各持有一端：
+//! - 接收缓冲区：中断写入，主程序读取
+//! - 发送缓冲区：主程序写入，中断读取
+//!
+//! `into_buffered` 把缓冲区绑定到一个串口，`release` 之前不能再用于其它串口；
+//! 主程序一端只能通过 `BufferedSerial` 访问，中断一端只能在所绑定串口的中断中访问。
+//!
+//! ```rust
+//! static BUFFERS: SerialBuffers<64, 64> = SerialBuffers::new();
+//!
+//! let serial = Serial::new(dp.USART1, rcc, Config::default()).unwrap();
+//! let mut serial = serial.into_buffered(&BUFFERS);
+//! unsafe { NVIC::unmask(Interrupt::USART1) };
+//!
+//! #[interrupt]
+//! fn USART1() {
+//!     BUFFERS.on_interrupt::<USART1>();
+//! }
+//! ```
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicU16, AtomicU8, AtomicUsize, Ordering};
+use core::task::{Context, Poll};
+
+use cortex_m::interrupt::InterruptNumber;
+use stm32f1::stm32f103::RCC;
+
+use super::rs485::DriverEnable;
+use super::{listen, read_word, write_word, Error, Event, Instance, Serial};
+use crate::executor::AtomicWaker;
+use crate::irq::current_vector;
+
+/// 单生产者、单消费者的环形缓冲区
+///
+/// 写入位置只由生产者修改，读取位置只由消费者修改，两端可以分别位于主程序和中断中，不需要临界区。
+/// 同一端不能同时在两个上下文中使用，所以 `push`、`pop` 为 `unsafe`，由调用者保证。
+/// 读写位置在 `0..2N` 内循环，用于区分空和满；`N` 为 0 时缓冲区既空又满。
+pub struct RingBuffer<const N: usize> {
+    buf: UnsafeCell<[u8; N]>,
+    /// 写入位置
+    head: AtomicUsize,
+    /// 读取位置
+    tail: AtomicUsize,
+}
+
+// 生产者只写 head 之后的空闲位置，消费者只读 tail 之后的已写入位置
+unsafe impl<const N: usize> Sync for RingBuffer<N> {}
+
+impl<const N: usize> Default for RingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RingBuffer<N> {
+    /// 创建一个空的缓冲区，用于 `static` 初始化
+    pub const fn new() -> Self {
+        RingBuffer {
+            buf: UnsafeCell::new([0; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// 容量
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// 已写入、尚未读取的字节数
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        Self::distance(head, tail)
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 是否已满
+    pub fn is_full(&self) -> bool {
+        self.len() >= N
+    }
+
+    /// 写入一个字节，已满时把字节原样返回
+    ///
+    /// # Safety
+    ///
+    /// 只能由唯一的生产者调用：同一时刻不能有另一个上下文（主程序或中断）也在调用 `push`
+    pub unsafe fn push(&self, byte: u8) -> Result<(), u8> {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        if Self::distance(head, tail) >= N {
+            return Err(byte);
+        }
+        (*self.buf.get())[head % N] = byte;
+        self.head.store(Self::next(head), Ordering::Release);
+        Ok(())
+    }
+
+    /// 读取一个字节
+    ///
+    /// # Safety
+    ///
+    /// 只能由唯一的消费者调用：同一时刻不能有另一个上下文（主程序或中断）也在调用 `pop`
+    pub unsafe fn pop(&self) -> Option<u8> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        let byte = (*self.buf.get())[tail % N];
+        self.tail.store(Self::next(tail), Ordering::Release);
+        Some(byte)
+    }
+
+    /// 从 `tail` 到 `head` 的字节数
+    fn distance(head: usize, tail: usize) -> usize {
+        if head >= tail {
+            head - tail
+        } else {
+            head + 2 * N - tail
+        }
+    }
+
+    /// 下一个读写位置
+    fn next(index: usize) -> usize {
+        if index + 1 >= 2 * N {
+            0
+        } else {
+            index + 1
+        }
+    }
+}
+
+/// 串口的接收和发送缓冲区
+pub struct SerialBuffers<const RX: usize, const TX: usize> {
+    rx: RingBuffer<RX>,
+    tx: RingBuffer<TX>,
+    /// 中断中记录的接收错误，每种错误占一位
+    errors: AtomicU8,
+    /// 等待接收数据的任务
+    rx_waker: AtomicWaker,
+    /// 等待发送缓冲区空间的任务
+    tx_waker: AtomicWaker,
+    /// RS-485 驱动器使能引脚，见 [`DriverEnable::encode`]，0 表示不使用
+    driver_enable: AtomicU16,
+    /// 绑定的串口编号，0 表示未绑定
+    owner: AtomicU8,
+}
+
+impl<const RX: usize, const TX: usize> Default for SerialBuffers<RX, TX> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const RX: usize, const TX: usize> SerialBuffers<RX, TX> {
+    /// 创建空的缓冲区，用于 `static` 初始化
+    pub const fn new() -> Self {
+        SerialBuffers {
+            rx: RingBuffer::new(),
+            tx: RingBuffer::new(),
+            errors: AtomicU8::new(0),
+            rx_waker: AtomicWaker::new(),
+            tx_waker: AtomicWaker::new(),
+            driver_enable: AtomicU16::new(0),
+            owner: AtomicU8::new(0),
+        }
+    }
+
+    /// 在串口中断处理函数中调用
+    /// 接收数据放入接收缓冲区，从发送缓冲区取数据写入 DR，并唤醒等待的异步任务；
+    /// 设置了 RS-485 驱动器使能引脚时，在发送完成（TC）后释放驱动器。
+    ///
+    /// 缓冲区未绑定到 `USART` 时不做任何处理；不在 `USART` 的中断处理函数中调用会 panic
+    pub fn on_interrupt<USART: Instance>(&self) {
+        if self.owner.load(Ordering::Acquire) != USART::NUMBER {
+            return;
+        }
+        if current_vector() != USART::INTERRUPT.number() + 16 {
+            panic!("SerialBuffers: on_interrupt called outside of the USART interrupt");
+        }
+        let usart = USART::regs();
+        let cr1 = usart.cr1.read();
+
+        if cr1.rxneie().bit_is_set() {
+            let received = match read_word::<USART>() {
+                Ok(word) => {
+                    // 接收缓冲区的生产者只有所绑定串口的中断
+                    if unsafe { self.rx.push(word as u8) }.is_err() {
+                        self.record_error(Error::BufferFull);
+                    }
+                    true
+                }
+                Err(Error::WouldBlock) => false,
+                Err(error) => {
+                    self.record_error(error);
+                    true
+                }
+            };
+            if received {
+                self.rx_waker.wake();
+            }
+        }
+
+        if cr1.txeie().bit_is_set() && usart.sr.read().txe().bit_is_set() {
+            // 发送缓冲区的消费者只有所绑定串口的中断
+            match unsafe { self.tx.pop() } {
+                Some(byte) => {
+                    let _ = write_word::<USART>(byte as u16);
+                }
+                None => {
+                    // 发送缓冲区已空，关闭 TXE 中断
+                    listen::<USART>(Event::Txe, false);
+                    // 等待最后一个字节发出后再释放驱动器
+                    if self.driver_enable().is_some() {
+                        listen::<USART>(Event::Tc, true);
+                    }
+                }
+            }
+            self.tx_waker.wake();
+        }
+
+        if cr1.tcie().bit_is_set() && usart.sr.read().tc().bit_is_set() {
+            listen::<USART>(Event::Tc, false);
+            // 主程序可能在此之前又写入了数据，此时保持驱动器使能
+            if self.tx.is_empty() {
+                if let Some(de) = self.driver_enable() {
+                    de.release();
+                }
+            }
+        }
+    }
+
+    /// RS-485 驱动器使能引脚
+    fn driver_enable(&self) -> Option<DriverEnable> {
+        DriverEnable::decode(self.driver_enable.load(Ordering::Acquire))
+    }
+
+    /// 记录接收错误
+    fn record_error(&self, error: Error) {
+        self.errors.fetch_or(error_bit(error), Ordering::Release);
+    }
+
+    /// 是否有已记录的接收错误
+    fn has_error(&self) -> bool {
+        self.errors.load(Ordering::Acquire) != 0
+    }
+
+    /// 取出一个已记录的接收错误，并清除该错误
+    fn take_error(&self) -> Option<Error> {
+        let errors = self.errors.load(Ordering::Acquire);
+        let error = [
+            Error::Overrun,
+            Error::BufferFull,
+            Error::Framing,
+            Error::Noise,
+            Error::Parity,
+        ]
+        .into_iter()
+        .find(|&error| errors & error_bit(error) != 0)?;
+        self.errors.fetch_and(!error_bit(error), Ordering::AcqRel);
+        Some(error)
+    }
+}
+
+/// 错误在错误位图中对应的位
+fn error_bit(error: Error) -> u8 {
+    match error {
+        Error::WouldBlock | Error::InvalidBaudrate => 0,
+        Error::Overrun => 1 << 0,
+        Error::Framing => 1 << 1,
+        Error::Noise => 1 << 2,
+        Error::Parity => 1 << 3,
+        Error::BufferFull => 1 << 4,
+    }
+}
+
+/// 中断驱动的缓冲串口
+/// 需要在串口中断处理函数中调用 [`SerialBuffers::on_interrupt`]；
+/// 持有接收缓冲区的消费者和发送缓冲区的生产者一端
+pub struct BufferedSerial<USART: 'static, const RX: usize, const TX: usize> {
+    serial: Serial<USART>,
+    buffers: &'static SerialBuffers<RX, TX>,
+}
+
+impl<USART: Instance> Serial<USART> {
+    /// 转换为中断驱动的缓冲串口，使能接收中断
+    /// 之后需要在 NVIC 中使能串口中断
+    ///
+    /// 缓冲区已经绑定到串口（尚未 `release`）时 panic
+    pub fn into_buffered<const RX: usize, const TX: usize>(
+        self,
+        buffers: &'static SerialBuffers<RX, TX>,
+    ) -> BufferedSerial<USART, RX, TX> {
+        if buffers
+            .owner
+            .compare_exchange(0, USART::NUMBER, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+        {
+            panic!("SerialBuffers: already bound to a USART");
+        }
+        // 丢弃之前残留的数据和错误
+        let _ = read_word::<USART>();
+        USART::regs()
+            .cr1
+            .modify(|_, w| w.rxneie().enabled().peie().enabled());
+        BufferedSerial {
+            serial: self,
+            buffers,
+        }
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> BufferedSerial<USART, RX, TX> {
+    /// 关闭串口中断，恢复为阻塞串口
+    /// 发送缓冲区中未发送的数据和接收缓冲区中未读取的数据会被丢弃，缓冲区解除绑定
+    pub fn release(self) -> Serial<USART> {
+        USART::regs().cr1.modify(|_, w| {
+            w.rxneie().disabled();
+            w.txeie().disabled();
+            w.tcie().disabled();
+            w.peie().disabled()
+        });
+        if let Some(de) = self.buffers.driver_enable() {
+            de.release();
+        }
+        self.buffers.driver_enable.store(0, Ordering::Release);
+        // 中断已关闭，不会再从发送缓冲区读取；丢弃两个缓冲区中剩余的数据
+        unsafe {
+            while self.buffers.tx.pop().is_some() {}
+            while self.buffers.rx.pop().is_some() {}
+        }
+        self.buffers.errors.store(0, Ordering::Release);
+        self.buffers.owner.store(0, Ordering::Release);
+        self.serial
+    }
+
+    /// 从接收缓冲区读取一个字节
+    fn pop_rx(&mut self) -> Option<u8> {
+        // `BufferedSerial` 是接收缓冲区唯一的消费者
+        unsafe { self.buffers.rx.pop() }
+    }
+
+    /// 写入一个字节到发送缓冲区
+    fn push_tx(&mut self, byte: u8) -> Result<(), u8> {
+        // `BufferedSerial` 是发送缓冲区唯一的生产者
+        unsafe { self.buffers.tx.push(byte) }
+    }
+
+    /// 读取一个字节
+    /// 先返回中断中记录的接收错误，没有数据时返回 [`Error::WouldBlock`]
+    pub fn read(&mut self) -> Result<u8, Error> {
+        if let Some(error) = self.buffers.take_error() {
+            return Err(error);
+        }
+        self.pop_rx().ok_or(Error::WouldBlock)
+    }
+
+    /// 读取接收缓冲区中已有的数据，返回读取的字节数
+    pub fn read_available(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if let Some(error) = self.buffers.take_error() {
+            return Err(error);
+        }
+        let mut count = 0;
+        for byte in buf.iter_mut() {
+            match self.pop_rx() {
+                Some(value) => *byte = value,
+                None => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// 接收缓冲区中的字节数
+    pub fn rx_len(&self) -> usize {
+        self.buffers.rx.len()
+    }
+
+    /// 是否有数据或接收错误可以读取
+    pub fn is_readable(&self) -> bool {
+        !self.buffers.rx.is_empty() || self.buffers.has_error()
+    }
+
+    /// 发送缓冲区是否还有空间
+    pub fn is_writable(&self) -> bool {
+        !self.buffers.tx.is_full()
+    }
+
+    /// 异步读取：注册唤醒器，没有数据时返回 `Poll::Pending`，由接收中断唤醒
+    pub fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        // 先注册再检查，避免丢失注册之前收到的数据
+        self.buffers.rx_waker.register(cx.waker());
+        match self.read_available(buf) {
+            Ok(0) => Poll::Pending,
+            result => Poll::Ready(result),
+        }
+    }
+
+    /// 写入一个字节到发送缓冲区，缓冲区已满时返回 [`Error::WouldBlock`]
+    pub fn write(&mut self, byte: u8) -> Result<(), Error> {
+        self.push_tx(byte).map_err(|_| Error::WouldBlock)?;
+        self.start_transmit();
+        Ok(())
+    }
+
+    /// 尽量多地写入发送缓冲区，返回写入的字节数
+    pub fn write_available(&mut self, bytes: &[u8]) -> usize {
+        let count = bytes
+            .iter()
+            .take_while(|&&byte| self.push_tx(byte).is_ok())
+            .count();
+        if count > 0 {
+            self.start_transmit();
+        }
+        count
+    }
+
+    /// 使能驱动器和 TXE 中断，由中断发送缓冲区中的数据
+    fn start_transmit(&mut self) {
+        if let Some(de) = self.buffers.driver_enable() {
+            de.assert();
+        }
+        listen::<USART>(Event::Txe, true);
+    }
+
+    /// 设置 RS-485 驱动器使能引脚，之后发送时自动使能驱动器，发送完成后在中断中释放
+    pub fn set_driver_enable(&mut self, rcc: &RCC, de: DriverEnable) {
+        self.flush();
+        de.configure(rcc);
+        self.buffers
+            .driver_enable
+            .store(de.encode(), Ordering::Release);
+    }
+
+    /// 不再控制驱动器使能引脚，并释放驱动器
+    pub fn clear_driver_enable(&mut self) {
+        self.flush();
+        if let Some(de) = self.buffers.driver_enable() {
+            de.release();
+        }
+        self.buffers.driver_enable.store(0, Ordering::Release);
+    }
+
+    /// 写入全部数据，发送缓冲区已满时等待中断发送
+    pub fn write_all(&mut self, bytes: &[u8]) {
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let count = self.write_available(rest);
+            rest = &rest[count..];
+        }
+    }
+
+    /// 发送缓冲区中未发送的字节数
+    pub fn tx_len(&self) -> usize {
+        self.buffers.tx.len()
+    }
+
+    /// 发送缓冲区中的数据是否已全部发出
+    pub fn is_transmit_complete(&self) -> bool {
+        self.buffers.tx.is_empty() && self.serial.is_transmit_complete()
+    }
+
+    /// 异步写入：注册唤醒器，发送缓冲区已满时返回 `Poll::Pending`，由发送中断唤醒
+    pub fn poll_write(&mut self, cx: &mut Context<'_>, bytes: &[u8]) -> Poll<usize> {
+        if bytes.is_empty() {
+            return Poll::Ready(0);
+        }
+        self.buffers.tx_waker.register(cx.waker());
+        match self.write_available(bytes) {
+            0 => Poll::Pending,
+            count => Poll::Ready(count),
+        }
+    }
+
+    /// 异步等待发送缓冲区发送完
+    /// 缓冲区为空后，最后一个字节从移位寄存器中发出最多需要一个字的时间，这里直接等待
+    pub fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.buffers.tx_waker.register(cx.waker());
+        if !self.buffers.tx.is_empty() {
+            return Poll::Pending;
+        }
+        self.serial.flush();
+        Poll::Ready(())
+    }
+
+    /// 等待发送缓冲区发送完，且最后一个字节从移位寄存器中发出
+    pub fn flush(&mut self) {
+        while !self.buffers.tx.is_empty() {}
+        self.serial.flush();
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> fmt::Write
+    for BufferedSerial<USART, RX, TX>
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试中只有一个线程，同时是唯一的生产者和消费者
+    fn push<const N: usize>(buffer: &RingBuffer<N>, byte: u8) -> Result<(), u8> {
+        unsafe { buffer.push(byte) }
+    }
+
+    fn pop<const N: usize>(buffer: &RingBuffer<N>) -> Option<u8> {
+        unsafe { buffer.pop() }
+    }
+
+    #[test]
+    fn full_and_empty_across_wrap() {
+        let buffer = RingBuffer::<4>::new();
+        assert!(buffer.is_empty());
+        assert_eq!(pop(&buffer), None);
+
+        // 读写位置多次越过末尾，每轮都先写满再读空
+        for round in 0..10u8 {
+            for i in 0..4 {
+                assert_eq!(push(&buffer, round * 4 + i), Ok(()));
+            }
+            assert!(buffer.is_full());
+            assert_eq!(buffer.len(), 4);
+            assert_eq!(push(&buffer, 0xFF), Err(0xFF));
+
+            for i in 0..4 {
+                assert_eq!(pop(&buffer), Some(round * 4 + i));
+            }
+            assert!(buffer.is_empty());
+            assert_eq!(pop(&buffer), None);
+        }
+    }
+
+    #[test]
+    fn partial_fill_across_wrap() {
+        // 容量不是 2 的幂时，读写位置回绕也不能错位
+        let buffer = RingBuffer::<3>::new();
+        let mut expected = 0u8;
+        let mut next = 0u8;
+        for _ in 0..20 {
+            for _ in 0..2 {
+                assert_eq!(push(&buffer, next), Ok(()));
+                next = next.wrapping_add(1);
+            }
+            assert_eq!(buffer.len(), 2);
+            assert!(!buffer.is_full());
+            for _ in 0..2 {
+                assert_eq!(pop(&buffer), Some(expected));
+                expected = expected.wrapping_add(1);
+            }
+            assert!(buffer.is_empty());
+        }
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let buffer = RingBuffer::<0>::new();
+        assert_eq!(buffer.capacity(), 0);
+        assert!(buffer.is_empty());
+        assert!(buffer.is_full());
+        assert_eq!(push(&buffer, 1), Err(1));
+        assert_eq!(pop(&buffer), None);
+    }
+}
@@ -0,0 +1,401 @@
+//! 串口 DMA 收发
+//!
+//! 发送使用普通模式的 DMA 通道，一次把整个缓冲区写入 DR；
+//! 接收使用循环模式的 DMA 通道，不停地把收到的数据写入接收缓冲区，
+//! 总线空闲（IDLE）时把上一次之后新收到的数据作为一帧交给应用，不需要逐字节中断，也不复制数据。
+//!
+//! 缓冲区所有权：
+//! - 发送：[`DmaTx::write`] 同时取得 `DmaTx` 和缓冲区的所有权，传输期间应用无法访问缓冲区，
+//!   [`TxTransfer::wait`] 等待传输完成后一起归还
+//! - 接收：[`DmaRx`] 在整个生命周期内独占接收缓冲区，[`DmaRx::release`] 时归还；
+//!   [`DmaRx::read_frame`] 返回的 [`Frame`] 直接借用缓冲区中的数据，`Frame` 存在期间不能读取下一帧。
+//!   DMA 仍会继续写入缓冲区的其余部分，所以必须在 DMA 写满一圈之前处理完 `Frame`，
+//!   缓冲区长度应大于处理一帧期间可能收到的数据量
+//! - 两次读取之间 DMA 恰好写满一圈时，由传输过半和传输完成两个标志同时置位判断，返回整个缓冲区；
+//!   超过一圈时较早的数据已被覆盖且无法检测，持续的数据流应调用 [`DmaRx::listen_dma`]，
+//!   每写入半个缓冲区就读取一次
+//!
+//! ```rust
+//! static mut RX_BUFFER: [u8; 256] = [0; 256];
+//!
+//! let serial = Serial::new(dp.USART1, rcc, config).unwrap();
+//! let rx_buffer = unsafe { &mut *core::ptr::addr_of_mut!(RX_BUFFER) };
+//! let (tx, mut rx) = serial.split_dma(rcc, rx_buffer);
+//!
+//! // 发送
+//! let (tx, _) = tx.write(b"hello\r\n".as_slice()).wait();
+//!
+//! // 在 USART1 中断中读取一帧
+//! if let Some(frame) = rx.read_frame() {
+//!     let (head, tail) = frame.as_slices();
+//! }
+//! ```
+
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use stm32f1::stm32f103::RCC;
+
+use super::{clear_idle, listen, Event, Instance, Serial};
+use crate::hardware::dma::{self, Flag};
+
+impl<USART: Instance> Serial<USART> {
+    /// 转换为 DMA 收发，接收缓冲区由 DMA 循环写入
+    /// 使能总线空闲中断，需要在 NVIC 中使能串口中断，并在中断中调用 [`DmaRx::read_frame`]
+    pub fn split_dma(
+        self,
+        rcc: &RCC,
+        rx_buffer: &'static mut [u8],
+    ) -> (DmaTx<USART>, DmaRx<USART>) {
+        assert!(
+            !rx_buffer.is_empty() && rx_buffer.len() <= u16::MAX as usize,
+            "invalid DMA buffer length"
+        );
+        dma::enable_clock(rcc);
+
+        let usart = USART::regs();
+        let dr = usart.dr.as_ptr() as u32;
+
+        // 发送通道：存储器到外设，地址递增
+        let channel = USART::TX_DMA;
+        channel.stop();
+        channel.regs().par.write(|w| unsafe { w.pa().bits(dr) });
+        channel.regs().cr.write(|w| {
+            w.dir().from_memory();
+            w.minc().enabled();
+            w.pl().medium()
+        });
+
+        // 接收通道：外设到存储器，地址递增，循环模式
+        let channel = USART::RX_DMA;
+        channel.stop();
+        channel.regs().par.write(|w| unsafe { w.pa().bits(dr) });
+        channel
+            .regs()
+            .mar
+            .write(|w| unsafe { w.ma().bits(rx_buffer.as_mut_ptr() as u32) });
+        channel
+            .regs()
+            .ndtr
+            .write(|w| w.ndt().bits(rx_buffer.len() as u16));
+        channel.regs().cr.write(|w| {
+            w.dir().from_peripheral();
+            w.minc().enabled();
+            w.circ().enabled();
+            w.pl().high()
+        });
+        compiler_fence(Ordering::Release);
+        channel.regs().cr.modify(|_, w| w.en().enabled());
+
+        usart.cr3.modify(|_, w| w.dmat().enabled().dmar().enabled());
+        clear_idle::<USART>();
+        listen::<USART>(Event::Idle, true);
+
+        let rx = DmaRx {
+            buf: rx_buffer.as_mut_ptr(),
+            len: rx_buffer.len(),
+            read: 0,
+            _usart: PhantomData,
+        };
+        (DmaTx { serial: self }, rx)
+    }
+}
+
+/// 可以用于 DMA 发送的缓冲区
+///
+/// # Safety
+///
+/// 缓冲区在整个传输期间必须有效且地址不变，移动实现该 trait 的值不能移动缓冲区本身
+pub unsafe trait TxBuffer {
+    /// 缓冲区数据
+    fn as_bytes(&self) -> &[u8];
+}
+
+unsafe impl TxBuffer for &'static [u8] {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+unsafe impl TxBuffer for &'static mut [u8] {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+unsafe impl TxBuffer for &'static str {
+    fn as_bytes(&self) -> &[u8] {
+        str::as_bytes(self)
+    }
+}
+
+/// DMA 发送端
+pub struct DmaTx<USART> {
+    serial: Serial<USART>,
+}
+
+impl<USART: Instance> DmaTx<USART> {
+    /// 启动 DMA 发送，传输期间持有发送端和缓冲区
+    /// 缓冲区长度超过 65535 时 panic
+    pub fn write<B: TxBuffer>(self, buf: B) -> TxTransfer<USART, B> {
+        let bytes = buf.as_bytes();
+        assert!(
+            bytes.len() <= u16::MAX as usize,
+            "invalid DMA buffer length"
+        );
+        let channel = USART::TX_DMA;
+        channel.stop();
+
+        if !bytes.is_empty() {
+            channel
+                .regs()
+                .mar
+                .write(|w| unsafe { w.ma().bits(bytes.as_ptr() as u32) });
+            channel
+                .regs()
+                .ndtr
+                .write(|w| w.ndt().bits(bytes.len() as u16));
+            // 清除发送完成标志，之后可以用它判断最后一个字节是否发出
+            // SR 的标志位为 rc_w0，直接写入只有 TC 为 0 的值，避免读改写清掉期间置位的 RXNE 等标志
+            USART::regs().sr.write(|w| unsafe { w.bits(!(1 << 6)) });
+            compiler_fence(Ordering::Release);
+            channel.regs().cr.modify(|_, w| w.en().enabled());
+        }
+
+        TxTransfer { tx: self, buf }
+    }
+
+    /// 等待最后一个字节从移位寄存器中发出
+    pub fn flush(&mut self) {
+        self.serial.flush();
+    }
+
+    /// 关闭 DMA 收发，归还串口和接收缓冲区
+    pub fn release(self, rx: DmaRx<USART>) -> (Serial<USART>, &'static mut [u8]) {
+        USART::TX_DMA.stop();
+        let buf = rx.release();
+        USART::regs()
+            .cr3
+            .modify(|_, w| w.dmat().disabled().dmar().disabled());
+        (self.serial, buf)
+    }
+}
+
+/// 正在进行的 DMA 发送
+pub struct TxTransfer<USART, B> {
+    tx: DmaTx<USART>,
+    buf: B,
+}
+
+impl<USART: Instance, B: TxBuffer> TxTransfer<USART, B> {
+    /// 所有数据是否已写入 DR
+    /// 此时最后一个字节可能仍在发送，需要等待发送完成时调用 [`DmaTx::flush`]
+    pub fn is_done(&self) -> bool {
+        let bytes = self.buf.as_bytes();
+        bytes.is_empty() || USART::TX_DMA.is_flag_set(Flag::TransferComplete)
+    }
+
+    /// 等待 DMA 传输完成，归还发送端和缓冲区
+    pub fn wait(self) -> (DmaTx<USART>, B) {
+        while !self.is_done() {}
+        USART::TX_DMA.stop();
+        compiler_fence(Ordering::Acquire);
+        (self.tx, self.buf)
+    }
+}
+
+/// DMA 接收端
+pub struct DmaRx<USART> {
+    buf: *mut u8,
+    len: usize,
+    /// 已交给应用的数据的结束位置
+    read: usize,
+    _usart: PhantomData<USART>,
+}
+
+// 接收缓冲区由 `DmaRx` 独占，可以移交给中断
+unsafe impl<USART> Send for DmaRx<USART> {}
+
+impl<USART: Instance> DmaRx<USART> {
+    /// 接收缓冲区长度
+    pub fn capacity(&self) -> usize {
+        self.len
+    }
+
+    /// DMA 下一次写入的位置
+    pub fn position(&self) -> usize {
+        // 循环模式下 NDTR 减到 0 后立即重新装载
+        (self.len - USART::RX_DMA.remaining() as usize) % self.len
+    }
+
+    /// 上一帧之后新收到的字节数
+    /// 恰好写满一圈时为缓冲区长度
+    pub fn available(&self) -> usize {
+        let channel = USART::RX_DMA;
+        let lapped =
+            channel.is_flag_set(Flag::HalfTransfer) && channel.is_flag_set(Flag::TransferComplete);
+        let (head, tail) = frame_ranges(self.read, self.position(), self.len, lapped);
+        head.len() + tail.len()
+    }
+
+    /// 在串口中断（总线空闲）或 DMA 中断（传输过半/完成）中调用
+    /// 检测到总线空闲或缓冲区过半/写满时，清除标志并返回上一帧之后新收到的数据
+    pub fn read_frame(&mut self) -> Option<Frame<'_>> {
+        let idle = USART::regs().sr.read().idle().bit_is_set();
+        if idle {
+            clear_idle::<USART>();
+        }
+        let (half, full) = Self::take_dma_flags();
+
+        if !(idle || half || full) {
+            return None;
+        }
+        let frame = self.take_frame(half && full);
+        (!frame.is_empty()).then_some(frame)
+    }
+
+    /// 不等待事件，直接返回上一帧之后新收到的数据，可能为空
+    /// 同时清除 DMA 传输过半/完成标志，用于判断是否恰好写满一圈
+    pub fn take(&mut self) -> Frame<'_> {
+        let (half, full) = Self::take_dma_flags();
+        self.take_frame(half && full)
+    }
+
+    /// 读取并清除传输过半、传输完成标志
+    /// 先清除标志再读取位置，清除之后的写入不会被漏掉
+    fn take_dma_flags() -> (bool, bool) {
+        let channel = USART::RX_DMA;
+        let half = channel.is_flag_set(Flag::HalfTransfer);
+        let full = channel.is_flag_set(Flag::TransferComplete);
+        if half {
+            channel.clear_flag(Flag::HalfTransfer);
+        }
+        if full {
+            channel.clear_flag(Flag::TransferComplete);
+        }
+        (half, full)
+    }
+
+    fn take_frame(&mut self, lapped: bool) -> Frame<'_> {
+        let start = self.read;
+        let end = self.position();
+        self.read = end;
+        compiler_fence(Ordering::Acquire);
+
+        let (head, tail) = frame_ranges(start, end, self.len, lapped);
+        unsafe {
+            Frame {
+                head: core::slice::from_raw_parts(self.buf.add(head.start), head.len()),
+                tail: core::slice::from_raw_parts(self.buf.add(tail.start), tail.len()),
+            }
+        }
+    }
+
+    /// 使能 DMA 传输过半和传输完成中断，持续不空闲的数据流也能及时交给应用
+    pub fn listen_dma(&mut self) {
+        USART::RX_DMA
+            .regs()
+            .cr
+            .modify(|_, w| w.htie().enabled().tcie().enabled());
+    }
+
+    /// 关闭 DMA 传输过半和传输完成中断
+    pub fn unlisten_dma(&mut self) {
+        USART::RX_DMA
+            .regs()
+            .cr
+            .modify(|_, w| w.htie().disabled().tcie().disabled());
+    }
+
+    /// 停止接收，归还接收缓冲区
+    pub fn release(self) -> &'static mut [u8] {
+        USART::RX_DMA.stop();
+        listen::<USART>(Event::Idle, false);
+        compiler_fence(Ordering::Acquire);
+        unsafe { core::slice::from_raw_parts_mut(self.buf, self.len) }
+    }
+}
+
+/// 从 `start` 到 `end` 的数据在缓冲区中的两段范围，没有回绕时第二段为空
+/// 位置不变时，`lapped`（传输过半和传输完成都发生过）表示恰好写满了一圈
+fn frame_ranges(
+    start: usize,
+    end: usize,
+    len: usize,
+    lapped: bool,
+) -> (Range<usize>, Range<usize>) {
+    if end == start && lapped {
+        (start..len, 0..start)
+    } else if end >= start {
+        (start..end, 0..0)
+    } else {
+        (start..len, 0..end)
+    }
+}
+
+/// 接收到的一帧数据，借用接收缓冲区
+/// 数据在缓冲区末尾回绕时由两段组成
+pub struct Frame<'a> {
+    head: &'a [u8],
+    tail: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// 字节数
+    pub fn len(&self) -> usize {
+        self.head.len() + self.tail.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 两段数据，没有回绕时第二段为空
+    pub fn as_slices(&self) -> (&'a [u8], &'a [u8]) {
+        (self.head, self.tail)
+    }
+
+    /// 按顺序遍历所有字节
+    pub fn iter(&self) -> impl Iterator<Item = u8> + 'a {
+        self.head.iter().chain(self.tail.iter()).copied()
+    }
+
+    /// 复制到 `buf`，返回复制的字节数
+    pub fn copy_to(&self, buf: &mut [u8]) -> usize {
+        let mut count = 0;
+        for (dst, src) in buf.iter_mut().zip(self.iter()) {
+            *dst = src;
+            count += 1;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_without_wrap() {
+        assert_eq!(frame_ranges(0, 0, 8, false), (0..0, 0..0));
+        assert_eq!(frame_ranges(0, 5, 8, false), (0..5, 0..0));
+        assert_eq!(frame_ranges(3, 7, 8, false), (3..7, 0..0));
+    }
+
+    #[test]
+    fn ranges_with_wrap() {
+        assert_eq!(frame_ranges(6, 2, 8, false), (6..8, 0..2));
+        assert_eq!(frame_ranges(6, 0, 8, false), (6..8, 0..0));
+    }
+
+    #[test]
+    fn full_lap() {
+        // 位置不变且两个标志都置位：整个缓冲区，从上次读取的位置开始
+        assert_eq!(frame_ranges(3, 3, 8, true), (3..8, 0..3));
+        assert_eq!(frame_ranges(0, 0, 8, true), (0..8, 0..0));
+        // 位置变化时标志不影响结果
+        assert_eq!(frame_ranges(3, 5, 8, true), (3..5, 0..0));
+    }
+}
@@ -0,0 +1,243 @@
+//! 串口的通用接口实现
+//!
+//! - `embedded_io::{Read, Write, ReadReady, WriteReady}`：阻塞的字节流读写
+//! - `embedded_hal_nb::serial::{Read, Write}`：非阻塞的单字节读写
+//! - `embedded_io_async::{Read, Write}`：异步读写，仅缓冲串口支持，由串口中断唤醒任务
+//!
+//! 第三方协议库（AT 指令解析、Modbus、GNSS 等）可以直接使用 [`Serial`] 或 [`BufferedSerial`]。
+//!
+//! ```rust
+//! use embedded_io_async::{Read, Write};
+//!
+//! // 串口自身的同名阻塞方法优先，需要显式调用 trait 方法
+//! async fn echo_task(mut serial: BufferedSerial<USART1, 64, 64>) {
+//!     let mut buf = [0; 16];
+//!     loop {
+//!         let n = Read::read(&mut serial, &mut buf).await.unwrap();
+//!         Write::write_all(&mut serial, &buf[..n]).await.unwrap();
+//!     }
+//! }
+//! ```
+
+use core::future::poll_fn;
+
+use embedded_hal_nb::nb;
+use embedded_hal_nb::serial;
+
+use super::buffered::BufferedSerial;
+use super::{Error, Instance, Serial};
+
+impl embedded_io::Error for Error {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            Error::Framing | Error::Noise | Error::Parity => embedded_io::ErrorKind::InvalidData,
+            Error::WouldBlock | Error::Overrun | Error::BufferFull => embedded_io::ErrorKind::Other,
+            Error::InvalidBaudrate => embedded_io::ErrorKind::InvalidInput,
+        }
+    }
+}
+
+impl serial::Error for Error {
+    fn kind(&self) -> serial::ErrorKind {
+        match self {
+            Error::Overrun | Error::BufferFull => serial::ErrorKind::Overrun,
+            Error::Framing => serial::ErrorKind::FrameFormat,
+            Error::Noise => serial::ErrorKind::Noise,
+            Error::Parity => serial::ErrorKind::Parity,
+            Error::WouldBlock | Error::InvalidBaudrate => serial::ErrorKind::Other,
+        }
+    }
+}
+
+/// 把 [`Error::WouldBlock`] 转换为 `nb::Error::WouldBlock`
+fn into_nb<T>(result: Result<T, Error>) -> nb::Result<T, Error> {
+    result.map_err(|error| match error {
+        Error::WouldBlock => nb::Error::WouldBlock,
+        error => nb::Error::Other(error),
+    })
+}
+
+impl<USART: Instance> embedded_io::ErrorType for Serial<USART> {
+    type Error = Error;
+}
+
+impl<USART: Instance> embedded_io::Read for Serial<USART> {
+    /// 阻塞等待第一个字节，之后只读取已经到达的字节
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let Some((first, rest)) = buf.split_first_mut() else {
+            return Ok(0);
+        };
+        *first = self.read_blocking()?;
+
+        let mut count = 1;
+        for byte in rest {
+            match Serial::read(self) {
+                Ok(value) => *byte = value,
+                Err(Error::WouldBlock) => break,
+                Err(error) => return Err(error),
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl<USART: Instance> embedded_io::ReadReady for Serial<USART> {
+    fn read_ready(&mut self) -> Result<bool, Error> {
+        Ok(self.is_readable())
+    }
+}
+
+impl<USART: Instance> embedded_io::Write for Serial<USART> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.write_all(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Serial::flush(self);
+        Ok(())
+    }
+}
+
+impl<USART: Instance> embedded_io::WriteReady for Serial<USART> {
+    fn write_ready(&mut self) -> Result<bool, Error> {
+        Ok(self.is_writable())
+    }
+}
+
+impl<USART: Instance> serial::ErrorType for Serial<USART> {
+    type Error = Error;
+}
+
+impl<USART: Instance> serial::Read<u8> for Serial<USART> {
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        into_nb(Serial::read(self))
+    }
+}
+
+impl<USART: Instance> serial::Write<u8> for Serial<USART> {
+    fn write(&mut self, word: u8) -> nb::Result<(), Error> {
+        into_nb(Serial::write(self, word))
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        if self.is_transmit_complete() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> embedded_io::ErrorType
+    for BufferedSerial<USART, RX, TX>
+{
+    type Error = Error;
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> embedded_io::Read
+    for BufferedSerial<USART, RX, TX>
+{
+    /// 阻塞等待接收缓冲区中有数据，之后读取已有的数据
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.read_available(buf)? {
+                0 => continue,
+                count => return Ok(count),
+            }
+        }
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> embedded_io::ReadReady
+    for BufferedSerial<USART, RX, TX>
+{
+    fn read_ready(&mut self) -> Result<bool, Error> {
+        Ok(self.is_readable())
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> embedded_io::Write
+    for BufferedSerial<USART, RX, TX>
+{
+    /// 阻塞等待发送缓冲区有空间，之后尽量多地写入
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.write_available(buf) {
+                0 => continue,
+                count => return Ok(count),
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        BufferedSerial::flush(self);
+        Ok(())
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> embedded_io::WriteReady
+    for BufferedSerial<USART, RX, TX>
+{
+    fn write_ready(&mut self) -> Result<bool, Error> {
+        Ok(self.is_writable())
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> serial::ErrorType
+    for BufferedSerial<USART, RX, TX>
+{
+    type Error = Error;
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> serial::Read<u8>
+    for BufferedSerial<USART, RX, TX>
+{
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        into_nb(BufferedSerial::read(self))
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> serial::Write<u8>
+    for BufferedSerial<USART, RX, TX>
+{
+    fn write(&mut self, word: u8) -> nb::Result<(), Error> {
+        into_nb(BufferedSerial::write(self, word))
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        if self.is_transmit_complete() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> embedded_io_async::Read
+    for BufferedSerial<USART, RX, TX>
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        poll_fn(|cx| self.poll_read(cx, buf)).await
+    }
+}
+
+impl<USART: Instance, const RX: usize, const TX: usize> embedded_io_async::Write
+    for BufferedSerial<USART, RX, TX>
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        Ok(poll_fn(|cx| self.poll_write(cx, buf)).await)
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        poll_fn(|cx| self.poll_flush(cx)).await;
+        Ok(())
+    }
+}
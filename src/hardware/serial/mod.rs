@@ -0,0 +1,465 @@
+//! 串口（USART）
+//!
+//! USART1 挂在 APB2（72MHz）上，USART2、USART3 挂在 APB1（36MHz）上，三者寄存器布局相同。
+//! 默认（未重映射）引脚：
+//! - USART1: TX PA9、RX PA10
+//! - USART2: TX PA2、RX PA3
+//! - USART3: TX PB10、RX PB11
+//!
+//! 波特率寄存器 BRR = 总线时钟 / 波特率，高 12 位为 USARTDIV 的整数部分，低 4 位为 1/16 的小数部分。
+//!
+//! ```rust
+//! let mut serial = Serial::new(dp.USART1, rcc, Config::default()).unwrap();
+//! writeln!(serial, "hello {}", 42).unwrap();
+//! let byte = serial.read_blocking().unwrap();
+//! ```
+
+pub mod buffered;
+pub mod dma;
+pub mod io;
+pub mod rs485;
+
+use core::fmt;
+
+use stm32f1::stm32f103::{usart1, Interrupt, RCC, USART1, USART2, USART3};
+
+use super::cfgr::{PCLK1_HZ, PCLK2_HZ};
+use super::dma::DmaChannel;
+use super::gpio::{set_pin_mode, PinMode, Port};
+
+/// 字长，包含校验位
+/// 8 位数据加校验位时应选择 `Bits9`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordLength {
+    Bits8,
+    Bits9,
+}
+
+/// 校验方式
+/// 使能校验后，字的最高位为校验位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+/// 停止位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopBits {
+    Stop1,
+    Stop0_5,
+    Stop2,
+    Stop1_5,
+}
+
+/// 串口配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// 波特率
+    pub baudrate: u32,
+    pub word_length: WordLength,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+}
+
+impl Default for Config {
+    /// 115200 8N1
+    fn default() -> Self {
+        Config {
+            baudrate: 115_200,
+            word_length: WordLength::Bits8,
+            parity: Parity::None,
+            stop_bits: StopBits::Stop1,
+        }
+    }
+}
+
+/// 串口错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 没有数据可读或发送寄存器非空，稍后重试
+    WouldBlock,
+    /// 上溢错误，接收寄存器中的数据未及时读取，之后的数据丢失
+    Overrun,
+    /// 帧错误，没有在预期位置检测到停止位，通常是波特率不匹配或线路断开
+    Framing,
+    /// 噪声错误，采样到的位电平不一致
+    Noise,
+    /// 校验错误
+    Parity,
+    /// 接收缓冲区已满，数据被丢弃
+    BufferFull,
+    /// 波特率为 0 或超出 BRR 可表示的范围（USARTDIV 须在 1 到 4095.9375 之间）
+    InvalidBaudrate,
+}
+
+/// 串口中断事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// 接收寄存器非空
+    Rxne,
+    /// 发送寄存器空
+    Txe,
+    /// 发送完成
+    Tc,
+    /// 总线空闲
+    Idle,
+}
+
+/// 串口外设
+pub trait Instance {
+    /// 寄存器组指针
+    const PTR: *const usart1::RegisterBlock;
+    /// 串口编号，USART1 为 1，以此类推
+    const NUMBER: u8;
+    /// 总线时钟频率
+    const CLOCK_HZ: u32;
+    /// 默认（未重映射）的 TX 引脚
+    const TX_PIN: (Port, u8);
+    /// 默认（未重映射）的 RX 引脚
+    const RX_PIN: (Port, u8);
+    /// 中断号
+    const INTERRUPT: Interrupt;
+    /// 发送请求连接的 DMA1 通道
+    const TX_DMA: DmaChannel;
+    /// 接收请求连接的 DMA1 通道
+    const RX_DMA: DmaChannel;
+
+    /// 使能并复位串口时钟
+    fn enable_clock(rcc: &RCC);
+
+    /// 寄存器组
+    fn regs() -> &'static usart1::RegisterBlock {
+        unsafe { &*Self::PTR }
+    }
+}
+
+impl Instance for USART1 {
+    const PTR: *const usart1::RegisterBlock = USART1::PTR;
+    const NUMBER: u8 = 1;
+    const CLOCK_HZ: u32 = PCLK2_HZ;
+    const TX_PIN: (Port, u8) = (Port::A, 9);
+    const RX_PIN: (Port, u8) = (Port::A, 10);
+    const INTERRUPT: Interrupt = Interrupt::USART1;
+    const TX_DMA: DmaChannel = DmaChannel::C4;
+    const RX_DMA: DmaChannel = DmaChannel::C5;
+
+    fn enable_clock(rcc: &RCC) {
+        rcc.apb2enr.modify(|_, w| w.usart1en().set_bit());
+        rcc.apb2rstr.modify(|_, w| w.usart1rst().set_bit());
+        rcc.apb2rstr.modify(|_, w| w.usart1rst().clear_bit());
+    }
+}
+
+impl Instance for USART2 {
+    const PTR: *const usart1::RegisterBlock = USART2::PTR;
+    const NUMBER: u8 = 2;
+    const CLOCK_HZ: u32 = PCLK1_HZ;
+    const TX_PIN: (Port, u8) = (Port::A, 2);
+    const RX_PIN: (Port, u8) = (Port::A, 3);
+    const INTERRUPT: Interrupt = Interrupt::USART2;
+    const TX_DMA: DmaChannel = DmaChannel::C7;
+    const RX_DMA: DmaChannel = DmaChannel::C6;
+
+    fn enable_clock(rcc: &RCC) {
+        rcc.apb1enr.modify(|_, w| w.usart2en().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.usart2rst().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.usart2rst().clear_bit());
+    }
+}
+
+impl Instance for USART3 {
+    const PTR: *const usart1::RegisterBlock = USART3::PTR;
+    const NUMBER: u8 = 3;
+    const CLOCK_HZ: u32 = PCLK1_HZ;
+    const TX_PIN: (Port, u8) = (Port::B, 10);
+    const RX_PIN: (Port, u8) = (Port::B, 11);
+    const INTERRUPT: Interrupt = Interrupt::USART3;
+    const TX_DMA: DmaChannel = DmaChannel::C2;
+    const RX_DMA: DmaChannel = DmaChannel::C3;
+
+    fn enable_clock(rcc: &RCC) {
+        rcc.apb1enr.modify(|_, w| w.usart3en().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.usart3rst().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.usart3rst().clear_bit());
+    }
+}
+
+/// 根据总线时钟和波特率计算 BRR
+/// BRR 即 16 倍过采样下以 1/16 为单位的 USARTDIV，四舍五入
+/// 波特率为 0 或四舍五入后超出 16..=0xFFFF 时返回 `Error::InvalidBaudrate`
+pub fn compute_brr(clock_hz: u32, baudrate: u32) -> Result<u16, Error> {
+    if baudrate == 0 {
+        return Err(Error::InvalidBaudrate);
+    }
+    let brr = (clock_hz as u64 + baudrate as u64 / 2) / baudrate as u64;
+    if (16..=0xFFFF).contains(&brr) {
+        Ok(brr as u16)
+    } else {
+        Err(Error::InvalidBaudrate)
+    }
+}
+
+/// BRR 对应的实际波特率
+pub fn brr_to_baudrate(clock_hz: u32, brr: u16) -> u32 {
+    clock_hz / brr.max(1) as u32
+}
+
+/// 串口
+pub struct Serial<USART> {
+    usart: USART,
+}
+
+impl<USART: Instance> Serial<USART> {
+    /// 使能时钟，配置 TX/RX 引脚，按配置使能收发
+    /// 波特率无效时不改动任何寄存器，返回 `Error::InvalidBaudrate`
+    pub fn new(usart: USART, rcc: &RCC, config: Config) -> Result<Self, Error> {
+        compute_brr(USART::CLOCK_HZ, config.baudrate)?;
+        USART::enable_clock(rcc);
+
+        let (port, pin) = USART::TX_PIN;
+        port.enable_clock(rcc);
+        set_pin_mode(port, pin, PinMode::AltPushPull);
+        let (port, pin) = USART::RX_PIN;
+        port.enable_clock(rcc);
+        // 上拉输入，线路断开时保持空闲电平
+        set_pin_mode(port, pin, PinMode::PullUpInput);
+
+        let mut serial = Serial { usart };
+        serial.apply_config(config)?;
+        Ok(serial)
+    }
+
+    /// 释放串口外设，关闭收发
+    pub fn release(self) -> USART {
+        USART::regs().cr1.reset();
+        self.usart
+    }
+
+    /// 重新配置波特率、字长、校验和停止位
+    /// 等待正在发送的数据发送完成后再修改
+    /// 波特率无效时保持原配置，返回 `Error::InvalidBaudrate`
+    pub fn apply_config(&mut self, config: Config) -> Result<(), Error> {
+        let brr = compute_brr(USART::CLOCK_HZ, config.baudrate)?;
+        let usart = USART::regs();
+        if usart.cr1.read().ue().bit_is_set() {
+            self.flush();
+        }
+        usart.cr1.modify(|_, w| w.ue().disabled());
+
+        usart.brr.write(|w| unsafe { w.bits(brr as u32) });
+        usart.cr2.modify(|_, w| match config.stop_bits {
+            StopBits::Stop1 => w.stop().stop1(),
+            StopBits::Stop0_5 => w.stop().stop0p5(),
+            StopBits::Stop2 => w.stop().stop2(),
+            StopBits::Stop1_5 => w.stop().stop1p5(),
+        });
+        usart.cr1.modify(|_, w| {
+            match config.word_length {
+                WordLength::Bits8 => w.m().m8(),
+                WordLength::Bits9 => w.m().m9(),
+            };
+            match config.parity {
+                Parity::None => w.pce().disabled(),
+                Parity::Even => w.pce().enabled().ps().even(),
+                Parity::Odd => w.pce().enabled().ps().odd(),
+            };
+            w.te().enabled().re().enabled().ue().enabled()
+        });
+        Ok(())
+    }
+
+    /// 实际波特率
+    pub fn baudrate(&self) -> u32 {
+        let brr = USART::regs().brr.read().bits() as u16;
+        brr_to_baudrate(USART::CLOCK_HZ, brr)
+    }
+
+    /// 使能中断
+    pub fn listen(&mut self, event: Event) {
+        listen::<USART>(event, true);
+    }
+
+    /// 关闭中断
+    pub fn unlisten(&mut self, event: Event) {
+        listen::<USART>(event, false);
+    }
+
+    /// 是否检测到总线空闲
+    pub fn is_idle(&self) -> bool {
+        USART::regs().sr.read().idle().bit_is_set()
+    }
+
+    /// 清除总线空闲标志
+    pub fn clear_idle(&mut self) {
+        clear_idle::<USART>();
+    }
+
+    /// 是否有数据或接收错误可以读取
+    pub fn is_readable(&self) -> bool {
+        let sr = USART::regs().sr.read();
+        sr.rxne().bit_is_set()
+            || sr.ore().bit_is_set()
+            || sr.fe().bit_is_set()
+            || sr.ne().bit_is_set()
+            || sr.pe().bit_is_set()
+    }
+
+    /// 发送寄存器是否为空
+    pub fn is_writable(&self) -> bool {
+        USART::regs().sr.read().txe().bit_is_set()
+    }
+
+    /// 最后一个字节是否已从移位寄存器中发出
+    pub fn is_transmit_complete(&self) -> bool {
+        USART::regs().sr.read().tc().bit_is_set()
+    }
+
+    /// 非阻塞读取一个字，没有数据时返回 [`Error::WouldBlock`]
+    /// 9 位字长且不带校验时返回 9 位数据，否则只有低 8 位有效
+    pub fn read_word(&mut self) -> Result<u16, Error> {
+        read_word::<USART>()
+    }
+
+    /// 非阻塞读取一个字节
+    pub fn read(&mut self) -> Result<u8, Error> {
+        read_word::<USART>().map(|word| word as u8)
+    }
+
+    /// 阻塞读取一个字节
+    pub fn read_blocking(&mut self) -> Result<u8, Error> {
+        loop {
+            match self.read() {
+                Err(Error::WouldBlock) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// 阻塞读满 `buf`，出错时立即返回
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        for byte in buf {
+            *byte = self.read_blocking()?;
+        }
+        Ok(())
+    }
+
+    /// 非阻塞写入一个字，发送寄存器非空时返回 [`Error::WouldBlock`]
+    pub fn write_word(&mut self, word: u16) -> Result<(), Error> {
+        write_word::<USART>(word)
+    }
+
+    /// 非阻塞写入一个字节
+    pub fn write(&mut self, byte: u8) -> Result<(), Error> {
+        write_word::<USART>(byte as u16)
+    }
+
+    /// 阻塞写入全部数据，不等待发送完成
+    pub fn write_all(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            while self.write(byte).is_err() {}
+        }
+    }
+
+    /// 等待最后一个字节从移位寄存器中发出
+    pub fn flush(&mut self) {
+        while !self.is_transmit_complete() {}
+    }
+}
+
+impl<USART: Instance> fmt::Write for Serial<USART> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// 使能或关闭中断
+fn listen<USART: Instance>(event: Event, enable: bool) {
+    USART::regs().cr1.modify(|_, w| match event {
+        Event::Rxne => w.rxneie().bit(enable),
+        Event::Txe => w.txeie().bit(enable),
+        Event::Tc => w.tcie().bit(enable),
+        Event::Idle => w.idleie().bit(enable),
+    });
+}
+
+/// 清除总线空闲标志
+/// 先读 SR 再读 DR
+fn clear_idle<USART: Instance>() {
+    let usart = USART::regs();
+    let _ = usart.sr.read();
+    let _ = usart.dr.read();
+}
+
+/// 检查错误标志并读取一个字
+/// 读 SR 后再读 DR 会同时清除 RXNE 和 PE/FE/NE/ORE 标志
+fn read_word<USART: Instance>() -> Result<u16, Error> {
+    let usart = USART::regs();
+    let sr = usart.sr.read();
+
+    let error = if sr.pe().bit_is_set() {
+        Some(Error::Parity)
+    } else if sr.fe().bit_is_set() {
+        Some(Error::Framing)
+    } else if sr.ne().bit_is_set() {
+        Some(Error::Noise)
+    } else if sr.ore().bit_is_set() {
+        Some(Error::Overrun)
+    } else {
+        None
+    };
+
+    if let Some(error) = error {
+        let _ = usart.dr.read();
+        return Err(error);
+    }
+    if sr.rxne().bit_is_clear() {
+        return Err(Error::WouldBlock);
+    }
+    Ok(usart.dr.read().dr().bits())
+}
+
+/// 发送寄存器为空时写入一个字
+fn write_word<USART: Instance>(word: u16) -> Result<(), Error> {
+    let usart = USART::regs();
+    if usart.sr.read().txe().bit_is_clear() {
+        return Err(Error::WouldBlock);
+    }
+    usart.dr.write(|w| w.dr().bits(word));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brr_rounds_to_nearest() {
+        // 72MHz / 115200 = 625
+        assert_eq!(compute_brr(72_000_000, 115_200), Ok(625));
+        // 36MHz / 115200 = 312.5，四舍五入为 313
+        assert_eq!(compute_brr(36_000_000, 115_200), Ok(313));
+        // 36MHz / 9600 = 3750
+        assert_eq!(compute_brr(36_000_000, 9_600), Ok(3750));
+        // 72MHz / 921600 = 78.125
+        assert_eq!(compute_brr(72_000_000, 921_600), Ok(78));
+        assert_eq!(brr_to_baudrate(36_000_000, 313), 115_015);
+    }
+
+    #[test]
+    fn brr_out_of_range() {
+        // USARTDIV 不小于 1
+        assert_eq!(
+            compute_brr(36_000_000, 36_000_000),
+            Err(Error::InvalidBaudrate)
+        );
+        assert_eq!(compute_brr(36_000_000, 2_250_000), Ok(16));
+        assert_eq!(compute_brr(36_000_000, 0), Err(Error::InvalidBaudrate));
+        // 72MHz / 300 = 240000，超出 16 位
+        assert_eq!(compute_brr(72_000_000, 300), Err(Error::InvalidBaudrate));
+        assert_eq!(compute_brr(72_000_000, 1_100), Ok(65_455));
+        assert_eq!(brr_to_baudrate(72_000_000, 0), 72_000_000);
+    }
+}
@@ -0,0 +1,269 @@
+//! RS-485 与单线半双工
+//!
+//! RS-485 收发器（如 MAX485）的 DE/RE 引脚在发送时使能驱动器，其余时间保持接收。
+//! 驱动器必须等到最后一个字节的停止位发出（TC 置位）后再释放，
+//! 只等到 TXE 会截断最后一个字节。
+//! - 阻塞方式：[`Rs485`] 发送前使能 DE，等待 TC 后释放
+//! - 中断方式：[`BufferedSerial::set_driver_enable`] 之后，发送缓冲区发完时使能 TC 中断，在中断中释放
+//!
+//! 单线半双工（HDSEL）模式下 TX 引脚同时用于收发，RX 引脚不使用，
+//! TX 引脚配置为复用开漏输出，总线上需要上拉电阻。
+//!
+//! ```rust
+//! // MAX485 的 DE 和 RE 连在一起接 PA1
+//! let de = DriverEnable::new(Port::A, 1, DePolarity::ActiveHigh);
+//! let mut bus = Serial::new(dp.USART2, rcc, Config::default())
+//!     .unwrap()
+//!     .into_rs485(rcc, de);
+//! bus.write_all(b"PING\r\n");
+//! ```
+//!
+//! [`BufferedSerial::set_driver_enable`]: super::buffered::BufferedSerial::set_driver_enable
+
+use core::fmt;
+
+use stm32f1::stm32f103::RCC;
+
+use super::{Error, Instance, Serial};
+use crate::hardware::gpio::{set_pin_mode, PinMode, Port};
+
+/// DE 引脚的有效电平
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DePolarity {
+    /// 高电平使能驱动器
+    ActiveHigh,
+    /// 低电平使能驱动器
+    ActiveLow,
+}
+
+/// 收发器的驱动器使能（DE）引脚
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriverEnable {
+    pub port: Port,
+    pub pin: u8,
+    pub polarity: DePolarity,
+}
+
+impl DriverEnable {
+    /// 需要调用 [`DriverEnable::configure`] 或交给串口驱动后才会配置引脚
+    pub fn new(port: Port, pin: u8, polarity: DePolarity) -> Self {
+        DriverEnable {
+            port,
+            pin,
+            polarity,
+        }
+    }
+
+    /// 配置为推挽输出，并释放驱动器
+    pub fn configure(self, rcc: &RCC) {
+        self.port.enable_clock(rcc);
+        self.release();
+        set_pin_mode(self.port, self.pin, PinMode::PushPullOutput);
+    }
+
+    /// 使能驱动器，进入发送状态
+    pub fn assert(self) {
+        self.set_level(self.polarity == DePolarity::ActiveHigh);
+    }
+
+    /// 释放驱动器，回到接收状态
+    pub fn release(self) {
+        self.set_level(self.polarity == DePolarity::ActiveLow);
+    }
+
+    /// 设置引脚电平
+    fn set_level(self, high: bool) {
+        // BSRR 低 16 位置位，高 16 位复位
+        let bit = if high { self.pin } else { self.pin + 16 };
+        self.port.regs().bsrr.write(|w| unsafe { w.bits(1 << bit) });
+    }
+
+    /// 编码为 16 位，用于在中断与主程序之间共享
+    /// 第 8 位为有效标志，第 7 位为极性，第 4~6 位为端口，第 0~3 位为引脚
+    pub(super) fn encode(self) -> u16 {
+        let polarity = match self.polarity {
+            DePolarity::ActiveHigh => 0,
+            DePolarity::ActiveLow => 1,
+        };
+        1 << 8 | polarity << 7 | (self.port.index() as u16) << 4 | (self.pin as u16 & 0xF)
+    }
+
+    /// 从 [`DriverEnable::encode`] 的结果解码
+    /// 没有有效标志或端口位无效时返回 `None`
+    pub(super) fn decode(bits: u16) -> Option<Self> {
+        if bits & (1 << 8) == 0 {
+            return None;
+        }
+        let port = match (bits >> 4) & 0b111 {
+            0 => Port::A,
+            1 => Port::B,
+            2 => Port::C,
+            3 => Port::D,
+            4 => Port::E,
+            _ => return None,
+        };
+        let polarity = if bits & (1 << 7) == 0 {
+            DePolarity::ActiveHigh
+        } else {
+            DePolarity::ActiveLow
+        };
+        Some(DriverEnable::new(port, (bits & 0xF) as u8, polarity))
+    }
+}
+
+impl<USART: Instance> Serial<USART> {
+    /// 转换为 RS-485 串口，由驱动自动控制 DE 引脚
+    pub fn into_rs485(self, rcc: &RCC, de: DriverEnable) -> Rs485<USART> {
+        de.configure(rcc);
+        Rs485 { serial: self, de }
+    }
+
+    /// 转换为单线半双工串口
+    /// TX 引脚配置为复用开漏输出，RX 引脚不再使用
+    pub fn into_half_duplex(mut self) -> HalfDuplex<USART> {
+        self.flush();
+        let (port, pin) = USART::TX_PIN;
+        set_pin_mode(port, pin, PinMode::AltOpenDrain);
+
+        let usart = USART::regs();
+        usart.cr1.modify(|_, w| w.ue().disabled());
+        // 半双工模式要求 LINEN、CLKEN、SCEN、IREN 均为 0
+        usart
+            .cr2
+            .modify(|_, w| w.linen().disabled().clken().disabled());
+        usart.cr3.modify(|_, w| {
+            w.scen().disabled();
+            w.iren().disabled();
+            w.hdsel().half_duplex()
+        });
+        usart.cr1.modify(|_, w| w.ue().enabled());
+
+        HalfDuplex { serial: self }
+    }
+}
+
+/// RS-485 串口
+pub struct Rs485<USART> {
+    serial: Serial<USART>,
+    de: DriverEnable,
+}
+
+impl<USART: Instance> Rs485<USART> {
+    /// 释放 DE 引脚，恢复为普通串口
+    pub fn release(self) -> Serial<USART> {
+        self.de.release();
+        self.serial
+    }
+
+    /// 驱动器使能引脚
+    pub fn driver_enable(&self) -> DriverEnable {
+        self.de
+    }
+
+    /// 使能驱动器，发送全部数据，等待最后一个字节发出后释放驱动器
+    pub fn write_all(&mut self, bytes: &[u8]) {
+        self.de.assert();
+        self.serial.write_all(bytes);
+        self.serial.flush();
+        self.de.release();
+    }
+
+    /// 非阻塞读取一个字节，没有数据时返回 [`Error::WouldBlock`]
+    pub fn read(&mut self) -> Result<u8, Error> {
+        self.serial.read()
+    }
+
+    /// 阻塞读取一个字节
+    pub fn read_blocking(&mut self) -> Result<u8, Error> {
+        self.serial.read_blocking()
+    }
+
+    /// 阻塞读满 `buf`，出错时立即返回
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        self.serial.read_exact(buf)
+    }
+}
+
+impl<USART: Instance> fmt::Write for Rs485<USART> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// 单线半双工串口
+pub struct HalfDuplex<USART> {
+    serial: Serial<USART>,
+}
+
+impl<USART: Instance> HalfDuplex<USART> {
+    /// 关闭半双工模式，恢复为普通串口
+    pub fn release(self) -> Serial<USART> {
+        let usart = USART::regs();
+        usart.cr1.modify(|_, w| w.ue().disabled());
+        usart.cr3.modify(|_, w| w.hdsel().full_duplex());
+        usart.cr1.modify(|_, w| w.ue().enabled());
+        let (port, pin) = USART::TX_PIN;
+        set_pin_mode(port, pin, PinMode::AltPushPull);
+        self.serial
+    }
+
+    /// 发送全部数据，等待最后一个字节发出
+    /// 发送期间关闭接收器，不会收到自己发出的数据
+    pub fn write_all(&mut self, bytes: &[u8]) {
+        let usart = USART::regs();
+        usart.cr1.modify(|_, w| w.re().disabled());
+        self.serial.write_all(bytes);
+        self.serial.flush();
+        usart.cr1.modify(|_, w| w.re().enabled());
+    }
+
+    /// 非阻塞读取一个字节，没有数据时返回 [`Error::WouldBlock`]
+    pub fn read(&mut self) -> Result<u8, Error> {
+        self.serial.read()
+    }
+
+    /// 阻塞读取一个字节
+    pub fn read_blocking(&mut self) -> Result<u8, Error> {
+        self.serial.read_blocking()
+    }
+
+    /// 阻塞读满 `buf`，出错时立即返回
+    pub fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        self.serial.read_exact(buf)
+    }
+}
+
+impl<USART: Instance> fmt::Write for HalfDuplex<USART> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn driver_enable_round_trip() {
+        for port in [Port::A, Port::B, Port::C, Port::D, Port::E] {
+            for pin in 0..=15 {
+                for polarity in [DePolarity::ActiveHigh, DePolarity::ActiveLow] {
+                    let de = DriverEnable::new(port, pin, polarity);
+                    assert_eq!(DriverEnable::decode(de.encode()), Some(de));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn driver_enable_invalid() {
+        // 没有有效标志
+        assert_eq!(DriverEnable::decode(0), None);
+        // 端口位 5~7 不对应任何端口
+        for port in 5..=7 {
+            assert_eq!(DriverEnable::decode(1 << 8 | port << 4), None);
+        }
+    }
+}
@@ -2,6 +2,18 @@
 
 use cortex_m::peripheral::{syst::SystClkSource, SYST};
 
+use super::delay::sysclk;
+
+/// SysTick 重载值的最大值（24 位）
+const MAX_RELOAD: u32 = 0x00FF_FFFF;
+
+/// SysTick 错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 节拍频率为 0、高于系统时钟，或低到重载值超出 24 位
+    FrequencyOutOfRange,
+}
+
 /// 毫秒延时
 /// 使用 SysTick 定时器来实现延时
 pub fn delay_ms(syst: &mut SYST, ms: u32) {
@@ -35,10 +47,33 @@ pub fn delay_ms(syst: &mut SYST, ms: u32) {
     syst.disable_counter();
 }
 
+/// 启动 SysTick 周期中断
+/// 以内核时钟为时钟源，每秒产生 `hz` 次 SysTick 异常，可作为软件定时器的节拍
+/// 72MHz 时 `hz` 的范围约为 5Hz~72MHz
+pub fn start_tick(syst: &mut SYST, hz: u32) -> Result<(), Error> {
+    // 每个节拍的时钟周期数，重载值为其减 1
+    let cycles = sysclk().checked_div(hz).ok_or(Error::FrequencyOutOfRange)?;
+    if !(2..=MAX_RELOAD + 1).contains(&cycles) {
+        return Err(Error::FrequencyOutOfRange);
+    }
+
+    // 内部时钟源，时钟频率为系统时钟
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(cycles - 1);
+    // 清除 SysTick 的当前计数值
+    syst.clear_current();
+    // 启用 SysTick 的中断
+    syst.enable_interrupt();
+    // 启用 SysTick 的计数器
+    syst.enable_counter();
+    Ok(())
+}
+
 /// 定义一个简单的延时函数，使用忙等待的方式
-/// 延时大约一秒钟
+/// 延时 `cycles` 秒，时钟频率取自 `set_clock`（或 `delay::init`）记录的系统时钟
 pub fn delay(cycles: u32) {
-    for _ in 0..cycles * 8_000_000 {
-        cortex_m::asm::nop();
+    // 逐秒延时，避免换算成毫秒时溢出
+    for _ in 0..cycles {
+        super::delay::delay_ms(1000);
     }
 }
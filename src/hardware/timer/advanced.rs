@@ -0,0 +1,363 @@
+//! 高级控制定时器（TIM1）
+//!
+//! 在通用定时器的基础上增加：
+//! - 互补输出：通道 1~3 的 CHxN（PB13、PB14、PB15）与 CHx 反相输出，用于驱动半桥
+//! - 死区：互补输出切换时，两路输出都保持无效电平的时间，由 BDTR 的 DTG 编码
+//! - 主输出使能（MOE）：所有输出的总开关
+//! - 刹车输入（BKIN，PB12）：有效时由硬件清除 MOE，输出立即进入安全状态
+//! - 自动输出使能（AOE）：刹车撤销后在下一个更新事件自动重新置位 MOE
+//!
+//! DTG 编码，tDTS 为 CR1 的 CKD 分频后的定时器时钟周期：
+//!
+//! | DTG[7:5] | 死区时间                    | 范围（tDTS）    |
+//! |----------|-----------------------------|-----------------|
+//! | 0xx      | DTG[7:0] × tDTS             | 0~127           |
+//! | 10x      | (64 + DTG[5:0]) × 2 × tDTS  | 128~254         |
+//! | 110      | (32 + DTG[4:0]) × 8 × tDTS  | 256~504         |
+//! | 111      | (32 + DTG[4:0]) × 16 × tDTS | 512~1008        |
+//!
+//! ```rust
+//! let mut pwm = Timer::new(dp.TIM1, rcc).pwm(rcc, &[Channel::C1], 20_000);
+//! // PA8 与 PB13 互补输出，死区 500ns
+//! pwm.enable_complementary(rcc, Channel::C1);
+//! pwm.set_dead_time_ns(500).unwrap();
+//! // PB12 低电平刹车
+//! pwm.enable_break(rcc, BreakConfig::default());
+//! ```
+
+use stm32f1::stm32f103::{tim1, RCC, TIM1};
+
+use super::pwm::{Polarity, Pwm};
+use super::{Channel, Instance};
+use crate::hardware::gpio::{set_pin_mode, PinMode, Port};
+
+/// 互补输出通道 1~3 默认（未重映射）连接的引脚
+const COMPLEMENTARY_PINS: [(Port, u8); 3] = [(Port::B, 13), (Port::B, 14), (Port::B, 15)];
+/// 刹车输入默认（未重映射）连接的引脚
+const BREAK_PIN: (Port, u8) = (Port::B, 12);
+/// SR 中的刹车中断标志 BIF
+const SR_BIF: u32 = 1 << 7;
+
+/// 死区和数字滤波器的时钟分频（CR1 的 CKD）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockDivision {
+    /// tDTS = tCK_INT
+    Div1,
+    /// tDTS = 2 × tCK_INT
+    Div2,
+    /// tDTS = 4 × tCK_INT
+    Div4,
+}
+
+impl ClockDivision {
+    /// 分频系数
+    pub const fn divisor(self) -> u32 {
+        match self {
+            ClockDivision::Div1 => 1,
+            ClockDivision::Div2 => 2,
+            ClockDivision::Div4 => 4,
+        }
+    }
+}
+
+/// 刹车输入极性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakPolarity {
+    /// 低电平刹车
+    ActiveLow,
+    /// 高电平刹车
+    ActiveHigh,
+}
+
+/// 刹车配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakConfig {
+    /// 刹车输入极性
+    pub polarity: BreakPolarity,
+    /// 刹车撤销后自动重新使能主输出
+    pub automatic_output: bool,
+    /// 刹车引脚输入模式
+    pub pin_mode: PinMode,
+}
+
+impl Default for BreakConfig {
+    fn default() -> Self {
+        BreakConfig {
+            polarity: BreakPolarity::ActiveLow,
+            automatic_output: false,
+            pin_mode: PinMode::PullUpInput,
+        }
+    }
+}
+
+/// 死区错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 死区时间超过 1008 × tDTS
+    DeadTimeTooLong,
+}
+
+/// 由纳秒计算 DTG 的值
+/// 结果向上取整，实际死区不短于 `ns`
+pub fn dead_time_dtg(clock_hz: u32, division: ClockDivision, ns: u32) -> Result<u8, Error> {
+    let dts_hz = (clock_hz / division.divisor()) as u64;
+    let ticks = (ns as u64 * dts_hz).div_ceil(1_000_000_000);
+
+    match ticks {
+        0..=127 => Ok(ticks as u8),
+        128..=254 => Ok(0b1000_0000 | (ticks.div_ceil(2) - 64) as u8),
+        255..=504 => Ok(0b1100_0000 | (ticks.div_ceil(8) - 32) as u8),
+        505..=1008 => Ok(0b1110_0000 | (ticks.div_ceil(16) - 32) as u8),
+        _ => Err(Error::DeadTimeTooLong),
+    }
+}
+
+/// DTG 对应的死区时间，单位为 tDTS
+pub fn dead_time_ticks(dtg: u8) -> u32 {
+    let dtg = dtg as u32;
+    if dtg & 0b1000_0000 == 0 {
+        dtg
+    } else if dtg & 0b1100_0000 == 0b1000_0000 {
+        (64 + (dtg & 0b11_1111)) * 2
+    } else if dtg & 0b1110_0000 == 0b1100_0000 {
+        (32 + (dtg & 0b1_1111)) * 8
+    } else {
+        (32 + (dtg & 0b1_1111)) * 16
+    }
+}
+
+/// DTG 对应的死区时间（纳秒）
+pub fn dead_time_ns(clock_hz: u32, division: ClockDivision, dtg: u8) -> u32 {
+    let dts_hz = (clock_hz / division.divisor()) as u64;
+    (dead_time_ticks(dtg) as u64 * 1_000_000_000 / dts_hz) as u32
+}
+
+impl Pwm<TIM1> {
+    /// 使能通道的互补输出 CHxN，默认高电平有效
+    /// 只有通道 1~3 有互补输出
+    pub fn enable_complementary(&mut self, rcc: &RCC, channel: Channel) {
+        let (port, pin) = complementary_pin(channel);
+        port.enable_clock(rcc);
+        rcc.apb2enr.modify(|_, w| w.afioen().set_bit());
+        set_pin_mode(port, pin, PinMode::AltPushPull);
+
+        // 使能互补输出后，MOE = 1 时关闭的输出保持无效电平而不是释放引脚
+        let tim1 = regs();
+        tim1.bdtr.modify(|_, w| w.ossr().idle_level());
+
+        let mask = 1 << (channel.index() * 4 + 2);
+        tim1.ccer.modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+    }
+
+    /// 关闭通道的互补输出
+    pub fn disable_complementary(&mut self, channel: Channel) {
+        complementary_pin(channel);
+        let mask = 1 << (channel.index() * 4 + 2);
+        regs()
+            .ccer
+            .modify(|r, w| unsafe { w.bits(r.bits() & !mask) });
+    }
+
+    /// 设置互补输出的极性
+    pub fn set_complementary_polarity(&mut self, channel: Channel, polarity: Polarity) {
+        complementary_pin(channel);
+        let mask = 1 << (channel.index() * 4 + 3);
+        regs().ccer.modify(|r, w| unsafe {
+            w.bits(match polarity {
+                Polarity::ActiveHigh => r.bits() & !mask,
+                Polarity::ActiveLow => r.bits() | mask,
+            })
+        });
+    }
+
+    /// 设置死区和滤波器的时钟分频
+    pub fn set_clock_division(&mut self, division: ClockDivision) {
+        regs().cr1.modify(|_, w| match division {
+            ClockDivision::Div1 => w.ckd().div1(),
+            ClockDivision::Div2 => w.ckd().div2(),
+            ClockDivision::Div4 => w.ckd().div4(),
+        });
+    }
+
+    /// 当前的时钟分频
+    pub fn clock_division(&self) -> ClockDivision {
+        match regs().cr1.read().ckd().bits() {
+            0b01 => ClockDivision::Div2,
+            0b10 => ClockDivision::Div4,
+            _ => ClockDivision::Div1,
+        }
+    }
+
+    /// 按纳秒设置死区时间，返回写入的 DTG
+    /// 超出当前分频的范围时可以先调用 `set_clock_division` 增大 tDTS
+    pub fn set_dead_time_ns(&mut self, ns: u32) -> Result<u8, Error> {
+        let dtg = dead_time_dtg(TIM1::CLOCK_HZ, self.clock_division(), ns)?;
+        self.set_dead_time_dtg(dtg);
+        Ok(dtg)
+    }
+
+    /// 直接设置 DTG
+    pub fn set_dead_time_dtg(&mut self, dtg: u8) {
+        regs().bdtr.modify(|_, w| unsafe { w.dtg().bits(dtg) });
+    }
+
+    /// 当前的死区时间（纳秒）
+    pub fn dead_time_ns(&self) -> u32 {
+        let dtg = regs().bdtr.read().dtg().bits();
+        dead_time_ns(TIM1::CLOCK_HZ, self.clock_division(), dtg)
+    }
+
+    /// 使能主输出
+    pub fn enable_main_output(&mut self) {
+        regs().bdtr.modify(|_, w| w.moe().enabled());
+    }
+
+    /// 关闭主输出，所有输出进入空闲状态
+    pub fn disable_main_output(&mut self) {
+        regs().bdtr.modify(|_, w| w.moe().disabled_idle());
+    }
+
+    /// 主输出是否使能，刹车有效时由硬件清除
+    pub fn is_main_output_enabled(&self) -> bool {
+        regs().bdtr.read().moe().is_enabled()
+    }
+
+    /// 设置自动输出使能
+    pub fn set_automatic_output(&mut self, enable: bool) {
+        regs().bdtr.modify(|_, w| w.aoe().bit(enable));
+    }
+
+    /// 使能刹车输入
+    pub fn enable_break(&mut self, rcc: &RCC, config: BreakConfig) {
+        let (port, pin) = BREAK_PIN;
+        port.enable_clock(rcc);
+        set_pin_mode(port, pin, config.pin_mode);
+
+        let tim1 = regs();
+        tim1.bdtr.modify(|_, w| {
+            w.bkp()
+                .bit(config.polarity == BreakPolarity::ActiveHigh)
+                .aoe()
+                .bit(config.automatic_output)
+                .bke()
+                .set_bit()
+        });
+        // 配置过程中可能已经置位刹车标志
+        clear_break_flag(tim1);
+    }
+
+    /// 关闭刹车输入
+    pub fn disable_break(&mut self) {
+        regs().bdtr.modify(|_, w| w.bke().clear_bit());
+    }
+
+    /// 是否发生过刹车
+    pub fn is_break(&self) -> bool {
+        regs().sr.read().bif().bit_is_set()
+    }
+
+    /// 清除刹车标志
+    pub fn clear_break(&mut self) {
+        clear_break_flag(regs());
+    }
+
+    /// 使能刹车中断（TIM1_BRK）
+    pub fn listen_break(&mut self) {
+        regs().dier.modify(|_, w| w.bie().set_bit());
+    }
+
+    /// 关闭刹车中断
+    pub fn unlisten_break(&mut self) {
+        regs().dier.modify(|_, w| w.bie().clear_bit());
+    }
+}
+
+/// 清除刹车标志
+/// SR 的标志写 0 清除、写 1 不变，直接写入以免清除同时置位的其它标志
+fn clear_break_flag(tim1: &tim1::RegisterBlock) {
+    tim1.sr.write(|w| unsafe { w.bits(!SR_BIF) });
+}
+
+fn regs() -> &'static tim1::RegisterBlock {
+    unsafe { &*TIM1::ptr() }
+}
+
+fn complementary_pin(channel: Channel) -> (Port, u8) {
+    match channel {
+        Channel::C4 => panic!("TIM1 channel 4 has no complementary output"),
+        _ => COMPLEMENTARY_PINS[channel.index()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK_HZ: u32 = 72_000_000;
+
+    #[test]
+    fn encodes_each_range() {
+        // 72MHz 下 tDTS 约为 13.9ns
+        assert_eq!(dead_time_dtg(CLOCK_HZ, ClockDivision::Div1, 0), Ok(0));
+        assert_eq!(dead_time_dtg(CLOCK_HZ, ClockDivision::Div1, 500), Ok(36));
+        assert_eq!(dead_time_dtg(CLOCK_HZ, ClockDivision::Div1, 1000), Ok(72));
+        // 128 个 tDTS
+        assert_eq!(dead_time_dtg(CLOCK_HZ, ClockDivision::Div1, 1777), Ok(0x80));
+        // 256 个 tDTS
+        assert_eq!(dead_time_dtg(CLOCK_HZ, ClockDivision::Div1, 3555), Ok(0xC0));
+        // 1008 个 tDTS
+        assert_eq!(
+            dead_time_dtg(CLOCK_HZ, ClockDivision::Div1, 14_000),
+            Ok(0xFF)
+        );
+        assert_eq!(
+            dead_time_dtg(CLOCK_HZ, ClockDivision::Div1, 14_001),
+            Err(Error::DeadTimeTooLong)
+        );
+        // 4 分频后范围扩大
+        assert_eq!(
+            dead_time_dtg(CLOCK_HZ, ClockDivision::Div4, 14_001),
+            Ok(0xBF)
+        );
+    }
+
+    #[test]
+    fn decodes_range_boundaries() {
+        assert_eq!(dead_time_ticks(0x7F), 127);
+        assert_eq!(dead_time_ticks(0x80), 128);
+        assert_eq!(dead_time_ticks(0xBF), 254);
+        assert_eq!(dead_time_ticks(0xC0), 256);
+        assert_eq!(dead_time_ticks(0xDF), 504);
+        assert_eq!(dead_time_ticks(0xE0), 512);
+        assert_eq!(dead_time_ticks(0xFF), 1008);
+    }
+
+    #[test]
+    fn encoded_dead_time_is_never_shorter() {
+        for division in [
+            ClockDivision::Div1,
+            ClockDivision::Div2,
+            ClockDivision::Div4,
+        ] {
+            let dts_hz = (CLOCK_HZ / division.divisor()) as u64;
+            for ticks in 0..=1008u64 {
+                let ns = (ticks * 1_000_000_000 / dts_hz) as u32;
+                let dtg = dead_time_dtg(CLOCK_HZ, division, ns).unwrap();
+                let actual = dead_time_ticks(dtg) as u64;
+                // 不短于请求值，且不超过所在区间的一个步长
+                assert!(actual * 1_000_000_000 >= ns as u64 * dts_hz);
+                assert!(actual <= ticks + 16, "{} -> {}", ticks, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn dtg_round_trip() {
+        for dtg in 0..=u8::MAX {
+            let ticks = dead_time_ticks(dtg) as u64;
+            let ns = (ticks * 1_000_000_000 / CLOCK_HZ as u64) as u32;
+            let encoded = dead_time_dtg(CLOCK_HZ, ClockDivision::Div1, ns).unwrap();
+            assert_eq!(dead_time_ticks(encoded), ticks as u32);
+        }
+    }
+}
@@ -0,0 +1,536 @@
+//! 输入捕获
+//!
+//! - 输入捕获：通道在指定边沿把计数值锁存到 CCRx，两次捕获之差即为信号周期，
+//!   计数器溢出的次数由更新标志统计，可以测量超过 16 位的周期
+//! - PWM 输入：两个通道映射到同一个输入，一个捕获上升沿、一个捕获下降沿，
+//!   上升沿通过从模式复位计数器，CCR 中直接得到周期和高电平时间
+//!
+//! ```rust
+//! // PA6 输入，计数频率 1MHz
+//! let mut input = Timer::new(dp.TIM3, rcc).pwm_input(rcc, Channel::C1, CaptureConfig::default(), 1_000_000);
+//! let measurement = input.read()?;
+//! println!("{} Hz, {}%", measurement.frequency_hz(), measurement.duty_percent());
+//! ```
+
+use stm32f1::stm32f103::{tim2, RCC};
+
+use super::{clear_flags, configure_input_pin, set_ccmr, Channel, Instance, Timer, SR_UIF};
+use crate::hardware::gpio::PinMode;
+
+/// 捕获边沿
+/// STM32F1 的通用定时器不支持双边沿捕获
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureEdge {
+    /// 上升沿
+    Rising,
+    /// 下降沿
+    Falling,
+}
+
+/// 输入捕获预分频，每 N 个有效边沿捕获一次
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapturePrescaler {
+    Div1,
+    Div2,
+    Div4,
+    Div8,
+}
+
+/// 输入捕获配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureConfig {
+    /// 捕获边沿，PWM 输入模式中为周期的起始边沿
+    pub edge: CaptureEdge,
+    /// 输入滤波器 ICxF（0~15），数值越大滤波越强
+    pub filter: u8,
+    /// 输入捕获预分频
+    pub prescaler: CapturePrescaler,
+    /// 引脚输入模式
+    pub pin_mode: PinMode,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        CaptureConfig {
+            edge: CaptureEdge::Rising,
+            filter: 0,
+            prescaler: CapturePrescaler::Div1,
+            pin_mode: PinMode::FloatingInput,
+        }
+    }
+}
+
+/// 输入捕获错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 尚未捕获到新的数据
+    WouldBlock,
+    /// 上次读取之后又发生了捕获，数据被覆盖
+    Overcapture,
+    /// 计数器溢出，信号周期超出测量范围
+    Overflow,
+    /// 等待捕获超时，信号可能已经停止
+    Timeout,
+}
+
+/// 测量结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    /// 周期（计数值）
+    pub period_ticks: u32,
+    /// 高电平时间（计数值），普通输入捕获时为 0
+    pub high_ticks: u32,
+    /// 计数频率
+    pub tick_hz: u32,
+}
+
+impl Measurement {
+    /// 周期（微秒）
+    pub fn period_us(&self) -> u32 {
+        ticks_to_us(self.period_ticks, self.tick_hz)
+    }
+
+    /// 高电平时间（微秒）
+    pub fn high_us(&self) -> u32 {
+        ticks_to_us(self.high_ticks, self.tick_hz)
+    }
+
+    /// 信号频率
+    pub fn frequency_hz(&self) -> u32 {
+        self.tick_hz / self.period_ticks.max(1)
+    }
+
+    /// 占空比（百分比）
+    pub fn duty_percent(&self) -> u8 {
+        (self.high_ticks as u64 * 100 / self.period_ticks.max(1) as u64).min(100) as u8
+    }
+}
+
+/// 计数值换算为微秒
+pub fn ticks_to_us(ticks: u32, tick_hz: u32) -> u32 {
+    (ticks as u64 * 1_000_000 / tick_hz.max(1) as u64) as u32
+}
+
+impl<TIM: Instance> Timer<TIM> {
+    /// 转换为输入捕获
+    /// `tick_hz` 为期望的计数频率，计数器以 0xFFFF 为周期自由计数，
+    /// 实际计数频率由 [`Capture::tick_hz`] 返回
+    pub fn capture(
+        self,
+        rcc: &RCC,
+        channels: &[Channel],
+        config: CaptureConfig,
+        tick_hz: u32,
+    ) -> Capture<TIM> {
+        let tim = TIM::regs();
+        let tick_hz = start_free_running::<TIM>(tim, tick_hz);
+
+        for &channel in channels {
+            configure_input_pin::<TIM>(rcc, channel, config.pin_mode);
+            // CCxS = 01，ICx 映射到 TIx
+            configure_input(tim, channel, 0b01, config);
+            set_input_polarity(tim, channel, config.edge);
+            enable_capture(tim, channel);
+        }
+
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        Capture {
+            timer: self,
+            tick_hz,
+        }
+    }
+
+    /// 转换为 PWM 输入
+    /// `input` 只能为通道 1 或通道 2，另一个通道映射到同一个引脚，
+    /// 测量范围为 65536 个计数值
+    pub fn pwm_input(
+        self,
+        rcc: &RCC,
+        input: Channel,
+        config: CaptureConfig,
+        tick_hz: u32,
+    ) -> PwmInput<TIM> {
+        let (period_channel, high_channel) = match input {
+            Channel::C1 => (Channel::C1, Channel::C2),
+            Channel::C2 => (Channel::C2, Channel::C1),
+            _ => panic!("PWM input is only available on channel 1 or 2"),
+        };
+        let opposite = match config.edge {
+            CaptureEdge::Rising => CaptureEdge::Falling,
+            CaptureEdge::Falling => CaptureEdge::Rising,
+        };
+
+        let tim = TIM::regs();
+        let tick_hz = start_free_running::<TIM>(tim, tick_hz);
+        configure_input_pin::<TIM>(rcc, input, config.pin_mode);
+
+        // 周期通道映射到自身的输入（CCxS = 01），高电平通道映射到另一个通道的输入（CCxS = 10）
+        configure_input(tim, period_channel, 0b01, config);
+        configure_input(tim, high_channel, 0b10, config);
+        set_input_polarity(tim, period_channel, config.edge);
+        set_input_polarity(tim, high_channel, opposite);
+
+        // 从模式：复位模式，触发源为输入通道的滤波后信号
+        tim.smcr.modify(|_, w| {
+            match input {
+                Channel::C1 => w.ts().ti1fp1(),
+                _ => w.ts().ti2fp2(),
+            };
+            w.sms().reset_mode()
+        });
+        // 只有计数器溢出才置位更新标志，从模式复位不置位
+        tim.cr1.modify(|_, w| w.urs().set_bit());
+
+        enable_capture(tim, period_channel);
+        enable_capture(tim, high_channel);
+        clear_flags(tim, SR_UIF);
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        PwmInput {
+            timer: self,
+            period_channel,
+            high_channel,
+            tick_hz,
+        }
+    }
+}
+
+/// 输入捕获
+pub struct Capture<TIM> {
+    timer: Timer<TIM>,
+    tick_hz: u32,
+}
+
+impl<TIM: Instance> Capture<TIM> {
+    /// 停止计数并释放定时器
+    pub fn release(self) -> Timer<TIM> {
+        stop(TIM::regs());
+        self.timer
+    }
+
+    /// 实际计数频率
+    pub fn tick_hz(&self) -> u32 {
+        self.tick_hz
+    }
+
+    /// 读取最近一次的捕获值（非阻塞）
+    pub fn read(&mut self, channel: Channel) -> Result<u16, Error> {
+        read_capture(TIM::regs(), channel)
+    }
+
+    /// 阻塞等待两次捕获，返回信号周期
+    /// 两次捕获之间计数器溢出的次数由更新标志统计，周期可以超过 16 位；
+    /// 信号停止时会一直等待，需要超时请使用 [`Capture::wait_period_timeout`]
+    pub fn wait_period(&mut self, channel: Channel) -> Result<Measurement, Error> {
+        self.wait_period_timeout(channel, u32::MAX)
+    }
+
+    /// 阻塞等待两次捕获，返回信号周期
+    /// 等待每次捕获时计数器溢出超过 `max_overflows` 次则返回 `Error::Timeout`，
+    /// 即最长等待约 `(max_overflows + 1) * 65536` 个计数值
+    pub fn wait_period_timeout(
+        &mut self,
+        channel: Channel,
+        max_overflows: u32,
+    ) -> Result<Measurement, Error> {
+        let tim = TIM::regs();
+
+        // 丢弃之前的捕获
+        let _ = read_capture(tim, channel);
+        let first = wait_capture(tim, channel, &mut 0, max_overflows)?;
+
+        // 第一次捕获之后发生的溢出属于本周期
+        let mut overflows = first.overflow_after as u32;
+        let second = wait_capture(tim, channel, &mut overflows, max_overflows)?;
+
+        let period = period_between(first.value, overflows, second.value)?;
+
+        Ok(Measurement {
+            period_ticks: period,
+            high_ticks: 0,
+            tick_hz: self.tick_hz,
+        })
+    }
+
+    /// 计数值换算为微秒
+    pub fn ticks_to_us(&self, ticks: u32) -> u32 {
+        ticks_to_us(ticks, self.tick_hz)
+    }
+}
+
+/// PWM 输入
+pub struct PwmInput<TIM> {
+    timer: Timer<TIM>,
+    period_channel: Channel,
+    high_channel: Channel,
+    tick_hz: u32,
+}
+
+impl<TIM: Instance> PwmInput<TIM> {
+    /// 停止计数并释放定时器
+    pub fn release(self) -> Timer<TIM> {
+        let tim = TIM::regs();
+        tim.smcr.modify(|_, w| w.sms().disabled());
+        stop(tim);
+        self.timer
+    }
+
+    /// 实际计数频率
+    pub fn tick_hz(&self) -> u32 {
+        self.tick_hz
+    }
+
+    /// 读取最近一个完整周期的测量结果（非阻塞）
+    /// 信号停止或周期超过 65536 个计数值时返回 `Error::Overflow`
+    pub fn read(&mut self) -> Result<Measurement, Error> {
+        let tim = TIM::regs();
+
+        if tim.sr.read().uif().bit_is_set() {
+            // 计数器溢出，丢弃本周期的数据
+            clear_flags(tim, SR_UIF);
+            let _ = read_capture(tim, self.period_channel);
+            return Err(Error::Overflow);
+        }
+
+        let period = read_capture(tim, self.period_channel)?;
+        // 周期捕获会复位计数器，捕获值加 1 才是完整的周期
+        let high = tim.ccr[self.high_channel.index()].read().ccr().bits();
+
+        Ok(Measurement {
+            period_ticks: period as u32 + 1,
+            high_ticks: high as u32 + 1,
+            tick_hz: self.tick_hz,
+        })
+    }
+
+    /// 读取最近一次的高电平（有效电平）时间（非阻塞）
+    /// 适用于超声波测距模块的回响信号等单个脉冲
+    pub fn read_pulse(&mut self) -> Result<u32, Error> {
+        let high = read_capture(TIM::regs(), self.high_channel)?;
+        Ok(high as u32 + 1)
+    }
+
+    /// 阻塞等待下一个完整周期的测量结果
+    pub fn wait(&mut self) -> Result<Measurement, Error> {
+        loop {
+            match self.read() {
+                Err(Error::WouldBlock) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// 计数值换算为微秒
+    pub fn ticks_to_us(&self, ticks: u32) -> u32 {
+        ticks_to_us(ticks, self.tick_hz)
+    }
+}
+
+/// 以接近 `tick_hz` 的计数频率、0xFFFF 的周期自由计数
+/// 返回预分频后的实际计数频率
+fn start_free_running<TIM: Instance>(tim: &tim2::RegisterBlock, tick_hz: u32) -> u32 {
+    let psc = free_running_psc(TIM::CLOCK_HZ, tick_hz);
+    tim.psc.write(|w| w.psc().bits(psc as u16));
+    tim.arr.write(|w| w.arr().bits(0xFFFF));
+    // 产生更新事件使预分频值生效
+    tim.egr.write(|w| w.ug().set_bit());
+    tim.sr.write(|w| unsafe { w.bits(0) });
+
+    TIM::CLOCK_HZ / (psc + 1)
+}
+
+/// 计数频率对应的预分频值，实际计数频率为 `clock_hz / (psc + 1)`
+pub(super) fn free_running_psc(clock_hz: u32, tick_hz: u32) -> u32 {
+    (clock_hz / tick_hz.max(1)).clamp(1, 0x1_0000) - 1
+}
+
+/// 配置输入通道：CCxS 选择输入映射，ICxPSC 预分频，ICxF 滤波
+fn configure_input(tim: &tim2::RegisterBlock, channel: Channel, ccs: u32, config: CaptureConfig) {
+    let psc = match config.prescaler {
+        CapturePrescaler::Div1 => 0b00,
+        CapturePrescaler::Div2 => 0b01,
+        CapturePrescaler::Div4 => 0b10,
+        CapturePrescaler::Div8 => 0b11,
+    };
+    let filter = (config.filter & 0b1111) as u32;
+
+    // 改变 CCxS 前必须关闭通道
+    disable_capture(tim, channel);
+    set_ccmr(tim, channel, 0xFF, (filter << 4) | (psc << 2) | ccs);
+}
+
+/// 设置输入极性，CCxP = 0 为上升沿，1 为下降沿
+fn set_input_polarity(tim: &tim2::RegisterBlock, channel: Channel, edge: CaptureEdge) {
+    let mask = 1 << (channel.index() * 4 + 1);
+    tim.ccer.modify(|r, w| unsafe {
+        w.bits(match edge {
+            CaptureEdge::Rising => r.bits() & !mask,
+            CaptureEdge::Falling => r.bits() | mask,
+        })
+    });
+}
+
+fn enable_capture(tim: &tim2::RegisterBlock, channel: Channel) {
+    let mask = 1 << (channel.index() * 4);
+    tim.ccer.modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+}
+
+fn disable_capture(tim: &tim2::RegisterBlock, channel: Channel) {
+    let mask = 1 << (channel.index() * 4);
+    tim.ccer.modify(|r, w| unsafe { w.bits(r.bits() & !mask) });
+}
+
+fn stop(tim: &tim2::RegisterBlock) {
+    tim.cr1.modify(|_, w| w.cen().clear_bit());
+    tim.ccer.reset();
+}
+
+/// 读取捕获值，读取 CCRx 会清除 CCxIF
+fn read_capture(tim: &tim2::RegisterBlock, channel: Channel) -> Result<u16, Error> {
+    let index = channel.index();
+    let sr = tim.sr.read().bits();
+    // CCxIF 为第 1~4 位，CCxOF 为第 9~12 位
+    let captured = sr & (1 << (index + 1)) != 0;
+    let overcaptured = sr & (1 << (index + 9)) != 0;
+
+    if !captured {
+        return Err(Error::WouldBlock);
+    }
+    let value = tim.ccr[index].read().ccr().bits();
+    if overcaptured {
+        let mask = !(1 << (index + 9));
+        tim.sr.write(|w| unsafe { w.bits(mask) });
+        return Err(Error::Overcapture);
+    }
+    Ok(value)
+}
+
+/// 一次捕获
+struct Captured {
+    /// 捕获值
+    value: u16,
+    /// 与捕获同时检测到的溢出发生在捕获之后，应计入下一段
+    overflow_after: bool,
+}
+
+/// 等待一次捕获，同时统计计数器溢出次数
+/// 溢出次数超过 `max_overflows` 时返回 `Error::Timeout`
+fn wait_capture(
+    tim: &tim2::RegisterBlock,
+    channel: Channel,
+    overflows: &mut u32,
+    max_overflows: u32,
+) -> Result<Captured, Error> {
+    loop {
+        let sr = tim.sr.read();
+        let captured = sr.bits() & (1 << (channel.index() + 1)) != 0;
+
+        if sr.uif().bit_is_set() {
+            clear_flags(tim, SR_UIF);
+            if captured {
+                let value = read_capture(tim, channel)?;
+                let overflow_after = overflow_after_capture(value);
+                if !overflow_after {
+                    *overflows += 1;
+                }
+                return Ok(Captured {
+                    value,
+                    overflow_after,
+                });
+            }
+            if *overflows >= max_overflows {
+                return Err(Error::Timeout);
+            }
+            *overflows += 1;
+            continue;
+        }
+
+        if captured {
+            return Ok(Captured {
+                value: read_capture(tim, channel)?,
+                overflow_after: false,
+            });
+        }
+    }
+}
+
+/// 溢出与捕获同时检测到时，判断溢出是否发生在捕获之后
+/// 捕获值较小说明捕获发生在溢出之后，较大说明捕获之后计数器才溢出
+fn overflow_after_capture(value: u16) -> bool {
+    value >= 0x8000
+}
+
+/// 两次捕获之间的计数值，`overflows` 为两次捕获之间的溢出次数
+fn period_between(first: u16, overflows: u32, second: u16) -> Result<u32, Error> {
+    (overflows as u64 * 0x1_0000 + second as u64)
+        .checked_sub(first as u64)
+        .filter(|period| *period <= u32::MAX as u64)
+        .map(|period| period as u32)
+        .ok_or(Error::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK_HZ: u32 = 72_000_000;
+
+    fn actual_tick_hz(tick_hz: u32) -> u32 {
+        CLOCK_HZ / (free_running_psc(CLOCK_HZ, tick_hz) + 1)
+    }
+
+    #[test]
+    fn exact_tick_rates() {
+        assert_eq!(actual_tick_hz(1_000_000), 1_000_000);
+        assert_eq!(actual_tick_hz(72_000_000), 72_000_000);
+        assert_eq!(actual_tick_hz(10_000), 10_000);
+    }
+
+    #[test]
+    fn inexact_tick_rates_report_actual() {
+        // 72MHz / 7MHz 向下取整为 10 分频
+        assert_eq!(actual_tick_hz(7_000_000), 7_200_000);
+        // 超过时钟频率时不分频
+        assert_eq!(actual_tick_hz(100_000_000), 72_000_000);
+        // 最大 65536 分频
+        assert_eq!(actual_tick_hz(1), 1098);
+        assert_eq!(actual_tick_hz(0), 1098);
+    }
+
+    #[test]
+    fn period_across_overflows() {
+        assert_eq!(period_between(0x1000, 0, 0x3000), Ok(0x2000));
+        assert_eq!(period_between(0xF000, 1, 0x1000), Ok(0x2000));
+        assert_eq!(period_between(0x1000, 2, 0x1000), Ok(0x2_0000));
+        assert_eq!(period_between(0x3000, 0, 0x1000), Err(Error::Overflow));
+        assert_eq!(period_between(0, 0x1_0000, 0), Err(Error::Overflow));
+    }
+
+    #[test]
+    fn overflow_pending_with_first_capture() {
+        // 第一次捕获在 0x9000，随后计数器溢出，两个标志同时被检测到；
+        // 第二次捕获在下一圈的 0xA000，实际周期为 0x11000
+        assert!(overflow_after_capture(0x9000));
+        let overflows = overflow_after_capture(0x9000) as u32;
+        assert_eq!(period_between(0x9000, overflows, 0xA000), Ok(0x1_1000));
+
+        // 捕获值较小：溢出在捕获之前，不计入本周期
+        assert!(!overflow_after_capture(0x0010));
+        let overflows = overflow_after_capture(0x0010) as u32;
+        assert_eq!(period_between(0x0010, overflows, 0x0110), Ok(0x100));
+    }
+
+    #[test]
+    fn measurement_uses_tick_hz() {
+        let measurement = Measurement {
+            period_ticks: 7_200,
+            high_ticks: 1_800,
+            tick_hz: actual_tick_hz(7_000_000),
+        };
+        assert_eq!(measurement.period_us(), 1_000);
+        assert_eq!(measurement.high_us(), 250);
+        assert_eq!(measurement.frequency_hz(), 1_000);
+        assert_eq!(measurement.duty_percent(), 25);
+    }
+}
@@ -0,0 +1,268 @@
+//! 主/从定时器级联
+//!
+//! 主定时器通过 CR2 的 MMS 选择触发输出 TRGO，从定时器通过 SMCR 的 TS 选择内部触发 ITRx：
+//!
+//! | 从定时器 | ITR0 | ITR1 | ITR2 | ITR3 |
+//! |----------|------|------|------|------|
+//! | TIM1     | TIM5 | TIM2 | TIM3 | TIM4 |
+//! | TIM2     | TIM1 | TIM8 | TIM3 | TIM4 |
+//! | TIM3     | TIM1 | TIM2 | TIM5 | TIM4 |
+//! | TIM4     | TIM1 | TIM2 | TIM3 | TIM8 |
+//!
+//! - 32 位计数器：主定时器的更新事件作为 TRGO，从定时器工作在外部时钟模式 1，
+//!   主定时器每溢出一次，从定时器加 1，从定时器为高 16 位
+//! - 同步启动：主定时器的 CEN 作为 TRGO，从定时器工作在触发模式，
+//!   启动主定时器时从定时器同时启动，PWM 相位对齐
+//!
+//! ```rust
+//! // TIM2 为低 16 位，TIM3 为高 16 位，计数频率 1MHz
+//! let counter = Timer::new(dp.TIM2, rcc).chain(Timer::new(dp.TIM3, rcc), 1_000_000).unwrap();
+//! let ticks = counter.count();
+//! ```
+
+use super::capture::free_running_psc;
+use super::pwm::Pwm;
+use super::{Instance, Timer};
+
+/// 主模式，TRGO 的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MasterMode {
+    /// EGR 的 UG 位
+    Reset,
+    /// 计数器使能 CEN
+    Enable,
+    /// 更新事件
+    Update,
+    /// 捕获/比较脉冲
+    ComparePulse,
+    /// OC1REF
+    CompareOc1,
+    /// OC2REF
+    CompareOc2,
+    /// OC3REF
+    CompareOc3,
+    /// OC4REF
+    CompareOc4,
+}
+
+/// 级联错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 从定时器没有连接到主定时器的内部触发
+    NotConnected,
+}
+
+/// 从定时器 `slave` 上连接主定时器 `master` 的内部触发 ITRx
+pub fn internal_trigger(slave: u8, master: u8) -> Option<u8> {
+    // 按从定时器列出 ITR0~ITR3 连接的主定时器
+    let masters = match slave {
+        1 => [5, 2, 3, 4],
+        2 => [1, 8, 3, 4],
+        3 => [1, 2, 5, 4],
+        4 => [1, 2, 3, 8],
+        _ => return None,
+    };
+    masters
+        .iter()
+        .position(|&m| m == master)
+        .map(|itr| itr as u8)
+}
+
+/// 设置主模式
+fn set_master_mode<TIM: Instance>(mode: MasterMode) {
+    TIM::regs().cr2.modify(|_, w| {
+        let mms = w.mms();
+        match mode {
+            MasterMode::Reset => mms.reset(),
+            MasterMode::Enable => mms.enable(),
+            MasterMode::Update => mms.update(),
+            MasterMode::ComparePulse => mms.compare_pulse(),
+            MasterMode::CompareOc1 => mms.compare_oc1(),
+            MasterMode::CompareOc2 => mms.compare_oc2(),
+            MasterMode::CompareOc3 => mms.compare_oc3(),
+            MasterMode::CompareOc4 => mms.compare_oc4(),
+        }
+    });
+}
+
+/// 选择主定时器的内部触发作为从定时器的触发输入
+fn select_master<M: Instance, S: Instance>() -> Result<(), Error> {
+    let itr = internal_trigger(S::NUMBER, M::NUMBER).ok_or(Error::NotConnected)?;
+    S::regs().smcr.modify(|_, w| unsafe { w.ts().bits(itr) });
+    Ok(())
+}
+
+impl<M: Instance> Timer<M> {
+    /// 设置主模式，选择输出到从定时器的 TRGO
+    pub fn set_master_mode(&mut self, mode: MasterMode) {
+        set_master_mode::<M>(mode);
+    }
+
+    /// 与 `slave` 级联为 32 位计数器
+    /// 本定时器以接近 `tick_hz` 的频率计数，为低 16 位；`slave` 对本定时器的溢出计数，为高 16 位；
+    /// 实际计数频率由 [`Counter32::tick_hz`] 返回
+    pub fn chain<S: Instance>(
+        self,
+        slave: Timer<S>,
+        tick_hz: u32,
+    ) -> Result<Counter32<M, S>, Error> {
+        select_master::<M, S>()?;
+
+        let low = M::regs();
+        let high = S::regs();
+
+        // 从定时器：外部时钟模式 1，时钟为主定时器的更新事件
+        high.psc.write(|w| w.psc().bits(0));
+        high.arr.write(|w| w.arr().bits(0xFFFF));
+        high.smcr.modify(|_, w| w.sms().ext_clock_mode());
+
+        // 主定时器：以 tick_hz 计数，更新事件作为 TRGO
+        let psc = free_running_psc(M::CLOCK_HZ, tick_hz);
+        low.psc.write(|w| w.psc().bits(psc as u16));
+        let tick_hz = M::CLOCK_HZ / (psc + 1);
+        low.arr.write(|w| w.arr().bits(0xFFFF));
+        // 主定时器的 UG 不能让从定时器多计一次，先产生更新事件再设置主模式
+        low.cr1.modify(|_, w| w.urs().set_bit());
+        low.egr.write(|w| w.ug().set_bit());
+        set_master_mode::<M>(MasterMode::Update);
+
+        high.egr.write(|w| w.ug().set_bit());
+        high.cnt.write(|w| w.cnt().bits(0));
+        low.cnt.write(|w| w.cnt().bits(0));
+        low.sr.write(|w| unsafe { w.bits(0) });
+        high.sr.write(|w| unsafe { w.bits(0) });
+
+        // 先启动从定时器，再启动主定时器
+        high.cr1.modify(|_, w| w.cen().set_bit());
+        low.cr1.modify(|_, w| w.cen().set_bit());
+
+        Ok(Counter32 {
+            low: self,
+            high: slave,
+            tick_hz,
+        })
+    }
+}
+
+/// 两个 16 位定时器级联的 32 位计数器
+pub struct Counter32<M, S> {
+    low: Timer<M>,
+    high: Timer<S>,
+    tick_hz: u32,
+}
+
+impl<M: Instance, S: Instance> Counter32<M, S> {
+    /// 停止计数并释放两个定时器
+    pub fn release(self) -> (Timer<M>, Timer<S>) {
+        M::regs().cr1.modify(|_, w| w.cen().clear_bit());
+        S::regs().cr1.modify(|_, w| w.cen().clear_bit());
+        M::regs().cr2.modify(|_, w| w.mms().reset());
+        S::regs().smcr.reset();
+        (self.low, self.high)
+    }
+
+    /// 实际计数频率
+    pub fn tick_hz(&self) -> u32 {
+        self.tick_hz
+    }
+
+    /// 读取 32 位计数值
+    /// 高 16 位读两次，两次不同说明读取期间低 16 位溢出，以第二次为准重新读取低 16 位
+    pub fn count(&self) -> u32 {
+        let low = M::regs();
+        let high = S::regs();
+
+        let mut hi = high.cnt.read().cnt().bits();
+        loop {
+            let lo = low.cnt.read().cnt().bits();
+            let hi_again = high.cnt.read().cnt().bits();
+            if hi == hi_again {
+                return (hi as u32) << 16 | lo as u32;
+            }
+            hi = hi_again;
+        }
+    }
+
+    /// 计数值换算为微秒
+    pub fn count_us(&self) -> u64 {
+        count_to_us(self.count(), self.tick_hz)
+    }
+
+    /// 计数清零
+    pub fn reset(&mut self) {
+        let low = M::regs();
+        let high = S::regs();
+        low.cr1.modify(|_, w| w.cen().clear_bit());
+        low.cnt.write(|w| w.cnt().bits(0));
+        high.cnt.write(|w| w.cnt().bits(0));
+        low.cr1.modify(|_, w| w.cen().set_bit());
+    }
+
+    /// 暂停计数
+    pub fn pause(&mut self) {
+        M::regs().cr1.modify(|_, w| w.cen().clear_bit());
+    }
+
+    /// 继续计数
+    pub fn resume(&mut self) {
+        M::regs().cr1.modify(|_, w| w.cen().set_bit());
+    }
+}
+
+impl<M: Instance> Pwm<M> {
+    /// 把 `slave` 设置为本定时器的从定时器
+    /// 两个定时器都会停止并清零，之后调用 `start_synchronized` 同时启动
+    pub fn synchronize<S: Instance>(&mut self, _slave: &mut Pwm<S>) -> Result<(), Error> {
+        select_master::<M, S>()?;
+
+        let master = M::regs();
+        let slave = S::regs();
+
+        master.cr1.modify(|_, w| w.cen().clear_bit());
+        slave.cr1.modify(|_, w| w.cen().clear_bit());
+        slave.cnt.write(|w| w.cnt().bits(0));
+
+        // 从定时器：触发模式，由主定时器的 CEN 启动
+        slave.smcr.modify(|_, w| w.sms().trigger_mode());
+        set_master_mode::<M>(MasterMode::Enable);
+        Ok(())
+    }
+
+    /// 计数器清零后启动，所有从定时器同时启动
+    /// 从定时器启动后不会随主定时器停止，重新对齐相位前需要再次调用 `synchronize`
+    pub fn start_synchronized(&mut self) {
+        let master = M::regs();
+        master.cr1.modify(|_, w| w.cen().clear_bit());
+        master.cnt.write(|w| w.cnt().bits(0));
+        master.cr1.modify(|_, w| w.cen().set_bit());
+    }
+}
+
+/// 计数值换算为微秒
+fn count_to_us(count: u32, tick_hz: u32) -> u64 {
+    count as u64 * 1_000_000 / tick_hz.max(1) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK_HZ: u32 = 72_000_000;
+
+    #[test]
+    fn non_divisor_tick_rate() {
+        // 请求 7MHz，72MHz 只能 10 分频，实际为 7.2MHz
+        let psc = free_running_psc(CLOCK_HZ, 7_000_000);
+        let tick_hz = CLOCK_HZ / (psc + 1);
+        assert_eq!(tick_hz, 7_200_000);
+        assert_eq!(count_to_us(7_200_000, tick_hz), 1_000_000);
+        assert_eq!(count_to_us(u32::MAX, tick_hz), 596_523_235);
+    }
+
+    #[test]
+    fn divisor_tick_rate() {
+        let psc = free_running_psc(CLOCK_HZ, 1_000_000);
+        assert_eq!(psc, 71);
+        assert_eq!(count_to_us(1_500, CLOCK_HZ / (psc + 1)), 1_500);
+    }
+}
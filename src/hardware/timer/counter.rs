@@ -0,0 +1,207 @@
+//! 外部时钟计数
+//!
+//! 计数器不再由内部时钟驱动，而是对引脚上的脉冲计数：
+//! - 外部时钟模式 1：SMS = 111，由触发输入 TRGI 驱动，可选 TI1F_ED（TI1 双边沿）、
+//!   TI1FP1、TI2FP2，经过通道的输入滤波器
+//! - 外部时钟模式 2：ECE = 1，由 ETR 引脚驱动，带极性选择、预分频和滤波器
+//!
+//! ```rust
+//! // PA0（TIM2_CH1）接对射式红外传感器，上升沿计数
+//! let mut counter = Timer::new(dp.TIM2, rcc).external_clock(rcc, CounterConfig::default());
+//! let count = counter.count();
+//! ```
+
+use stm32f1::stm32f103::RCC;
+
+use super::capture::CaptureEdge;
+use super::{
+    clear_flags, configure_etr_pin, configure_input_pin, set_ccmr, Channel, Instance, Timer, SR_UIF,
+};
+use crate::hardware::gpio::PinMode;
+
+/// 计数时钟源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterSource {
+    /// 外部时钟模式 1，通道 1 引脚的上升沿和下降沿都计数，忽略 `edge`
+    Ti1BothEdges,
+    /// 外部时钟模式 1，通道 1 引脚
+    Ti1,
+    /// 外部时钟模式 1，通道 2 引脚
+    Ti2,
+    /// 外部时钟模式 2，ETR 引脚
+    Etr,
+}
+
+/// ETR 预分频
+/// ETRP 的频率不能超过定时器时钟的 1/4，高频信号需要先分频
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EtrPrescaler {
+    Div1,
+    Div2,
+    Div4,
+    Div8,
+}
+
+/// 外部时钟计数配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterConfig {
+    /// 计数时钟源
+    pub source: CounterSource,
+    /// 计数边沿
+    pub edge: CaptureEdge,
+    /// 输入滤波器 ICxF/ETF（0~15），用于消除传感器输出的抖动
+    pub filter: u8,
+    /// ETR 预分频，只对 `CounterSource::Etr` 有效
+    pub etr_prescaler: EtrPrescaler,
+    /// 引脚输入模式
+    pub pin_mode: PinMode,
+}
+
+impl Default for CounterConfig {
+    fn default() -> Self {
+        CounterConfig {
+            source: CounterSource::Ti1,
+            edge: CaptureEdge::Rising,
+            filter: 0b0011,
+            etr_prescaler: EtrPrescaler::Div1,
+            pin_mode: PinMode::PullUpInput,
+        }
+    }
+}
+
+impl<TIM: Instance> Timer<TIM> {
+    /// 转换为外部时钟计数器
+    /// 每个有效边沿计数加 1，计数器在 0~0xFFFF 之间循环
+    pub fn external_clock(self, rcc: &RCC, config: CounterConfig) -> Counter<TIM> {
+        let tim = TIM::regs();
+        let filter = config.filter & 0b1111;
+        let falling = config.edge == CaptureEdge::Falling;
+
+        match config.source {
+            CounterSource::Ti1BothEdges | CounterSource::Ti1 | CounterSource::Ti2 => {
+                let channel = match config.source {
+                    CounterSource::Ti2 => Channel::C2,
+                    _ => Channel::C1,
+                };
+                configure_input_pin::<TIM>(rcc, channel, config.pin_mode);
+
+                // CCxS = 01 映射到 TIx，设置输入滤波器
+                set_ccmr(tim, channel, 0xFF, ((filter as u32) << 4) | 0b01);
+                // CCxP 选择有效边沿，不需要使能捕获
+                tim.ccer.modify(|_, w| match channel {
+                    Channel::C1 => w.cc1e().clear_bit().cc1p().bit(falling),
+                    _ => w.cc2e().clear_bit().cc2p().bit(falling),
+                });
+
+                tim.smcr.modify(|_, w| {
+                    match config.source {
+                        CounterSource::Ti1BothEdges => w.ts().ti1f_ed(),
+                        CounterSource::Ti1 => w.ts().ti1fp1(),
+                        _ => w.ts().ti2fp2(),
+                    };
+                    w.ece().disabled().sms().ext_clock_mode()
+                });
+            }
+            CounterSource::Etr => {
+                configure_etr_pin::<TIM>(rcc, config.pin_mode);
+
+                tim.smcr.modify(|_, w| {
+                    match config.etr_prescaler {
+                        EtrPrescaler::Div1 => w.etps().div1(),
+                        EtrPrescaler::Div2 => w.etps().div2(),
+                        EtrPrescaler::Div4 => w.etps().div4(),
+                        EtrPrescaler::Div8 => w.etps().div8(),
+                    };
+                    w.etf().bits(filter);
+                    // ETP = 1 时 ETR 反相，下降沿有效
+                    w.etp().bit(falling).ece().enabled().sms().disabled()
+                });
+            }
+        }
+
+        tim.psc.write(|w| w.psc().bits(0));
+        tim.arr.write(|w| w.arr().bits(0xFFFF));
+        // 只有计数器溢出才置位更新标志
+        tim.cr1.modify(|_, w| w.urs().set_bit());
+        tim.egr.write(|w| w.ug().set_bit());
+        clear_flags(tim, SR_UIF);
+        tim.cnt.write(|w| w.cnt().bits(0));
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        Counter {
+            timer: self,
+            last_count: 0,
+            total: 0,
+        }
+    }
+}
+
+/// 外部时钟计数器
+pub struct Counter<TIM> {
+    timer: Timer<TIM>,
+    /// 上次读取的计数值
+    last_count: u16,
+    /// 扩展后的计数
+    total: u32,
+}
+
+impl<TIM: Instance> Counter<TIM> {
+    /// 停止计数并释放定时器
+    pub fn release(self) -> Timer<TIM> {
+        let tim = TIM::regs();
+        tim.cr1.modify(|_, w| w.cen().clear_bit());
+        tim.dier.modify(|_, w| w.uie().clear_bit());
+        tim.smcr.reset();
+        self.timer
+    }
+
+    /// 当前计数值
+    pub fn count(&self) -> u16 {
+        TIM::regs().cnt.read().cnt().bits()
+    }
+
+    /// 扩展到 32 位的累计计数
+    /// 按最近两次读数的差值累加，需要在计数器回绕之前再次调用
+    pub fn total(&mut self) -> u32 {
+        let count = self.count();
+        let delta = count.wrapping_sub(self.last_count);
+        self.last_count = count;
+        self.total = self.total.wrapping_add(delta as u32);
+        self.total
+    }
+
+    /// 计数清零
+    pub fn reset(&mut self) {
+        TIM::regs().cnt.write(|w| w.cnt().bits(0));
+        self.last_count = 0;
+        self.total = 0;
+    }
+
+    /// 设置自动重装载值，每计数 `reload + 1` 次产生一次更新事件
+    /// 修改后 `total` 的扩展不再有效
+    pub fn set_reload(&mut self, reload: u16) {
+        TIM::regs().arr.write(|w| w.arr().bits(reload));
+    }
+
+    /// 使能更新中断，计数达到自动重装载值后触发
+    pub fn listen(&mut self) {
+        let tim = TIM::regs();
+        clear_flags(tim, SR_UIF);
+        tim.dier.modify(|_, w| w.uie().set_bit());
+    }
+
+    /// 关闭更新中断
+    pub fn unlisten(&mut self) {
+        TIM::regs().dier.modify(|_, w| w.uie().clear_bit());
+    }
+
+    /// 是否发生了更新事件
+    pub fn is_update(&self) -> bool {
+        TIM::regs().sr.read().uif().bit_is_set()
+    }
+
+    /// 清除更新标志
+    pub fn clear_update(&mut self) {
+        clear_flags(TIM::regs(), SR_UIF);
+    }
+}
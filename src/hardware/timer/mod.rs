@@ -0,0 +1,268 @@
+//! 定时器
+//!
+//! TIM1 为高级控制定时器，TIM2~TIM4 为通用定时器。
+//! 两者的控制、计数、预分频和捕获/比较寄存器布局相同，这里统一按通用定时器的寄存器组访问，
+//! TIM1 特有的重复计数器和刹车/死区寄存器单独处理。
+//!
+//! ```rust
+//! let timer = Timer::new(dp.TIM2, rcc);
+//! // PA0 输出 1kHz PWM
+//! let mut pwm = timer.pwm(rcc, &[Channel::C1], 1_000);
+//! pwm.set_duty(Channel::C1, pwm.get_max_duty() / 2);
+//! ```
+
+pub mod advanced;
+pub mod capture;
+pub mod chain;
+pub mod counter;
+pub mod opm;
+pub mod pwm;
+pub mod qei;
+
+use stm32f1::stm32f103::{tim2, RCC, TIM1, TIM2, TIM3, TIM4};
+
+use super::cfgr::{TIMCLK1_HZ, TIMCLK2_HZ};
+use super::gpio::{set_pin_mode, PinMode, Port};
+
+/// 定时器通道
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    C1,
+    C2,
+    C3,
+    C4,
+}
+
+impl Channel {
+    /// 通道序号，从 0 开始
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 定时器外设
+pub trait Instance {
+    /// 寄存器组指针，TIM1 的通用部分与 TIM2 布局相同
+    const PTR: *const tim2::RegisterBlock;
+    /// 定时器编号，TIM1 为 1，以此类推
+    const NUMBER: u8;
+    /// 计数器输入时钟频率
+    const CLOCK_HZ: u32;
+    /// 通道 1~4 默认（未重映射）连接的引脚
+    const CHANNEL_PINS: [(Port, u8); 4];
+    /// 外部触发输入 ETR 默认连接的引脚
+    const ETR_PIN: (Port, u8);
+
+    /// 使能并复位定时器时钟
+    fn enable_clock(rcc: &RCC);
+
+    /// 使能主输出
+    /// 只有高级控制定时器需要置位 BDTR 的 MOE 位，通用定时器不需要
+    fn enable_main_output() {}
+
+    /// 寄存器组
+    fn regs() -> &'static tim2::RegisterBlock {
+        unsafe { &*Self::PTR }
+    }
+}
+
+impl Instance for TIM1 {
+    const PTR: *const tim2::RegisterBlock = TIM1::PTR as *const _;
+    const NUMBER: u8 = 1;
+    const CLOCK_HZ: u32 = TIMCLK2_HZ;
+    const CHANNEL_PINS: [(Port, u8); 4] =
+        [(Port::A, 8), (Port::A, 9), (Port::A, 10), (Port::A, 11)];
+    const ETR_PIN: (Port, u8) = (Port::A, 12);
+
+    fn enable_clock(rcc: &RCC) {
+        rcc.apb2enr.modify(|_, w| w.tim1en().set_bit());
+        rcc.apb2rstr.modify(|_, w| w.tim1rst().set_bit());
+        rcc.apb2rstr.modify(|_, w| w.tim1rst().clear_bit());
+    }
+
+    fn enable_main_output() {
+        let tim1 = unsafe { &*TIM1::ptr() };
+        tim1.bdtr.modify(|_, w| w.moe().set_bit());
+    }
+}
+
+impl Instance for TIM2 {
+    const PTR: *const tim2::RegisterBlock = TIM2::PTR;
+    const NUMBER: u8 = 2;
+    const CLOCK_HZ: u32 = TIMCLK1_HZ;
+    const CHANNEL_PINS: [(Port, u8); 4] = [(Port::A, 0), (Port::A, 1), (Port::A, 2), (Port::A, 3)];
+    const ETR_PIN: (Port, u8) = (Port::A, 0);
+
+    fn enable_clock(rcc: &RCC) {
+        rcc.apb1enr.modify(|_, w| w.tim2en().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.tim2rst().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.tim2rst().clear_bit());
+    }
+}
+
+impl Instance for TIM3 {
+    const PTR: *const tim2::RegisterBlock = TIM3::PTR;
+    const NUMBER: u8 = 3;
+    const CLOCK_HZ: u32 = TIMCLK1_HZ;
+    const CHANNEL_PINS: [(Port, u8); 4] = [(Port::A, 6), (Port::A, 7), (Port::B, 0), (Port::B, 1)];
+    const ETR_PIN: (Port, u8) = (Port::D, 2);
+
+    fn enable_clock(rcc: &RCC) {
+        rcc.apb1enr.modify(|_, w| w.tim3en().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.tim3rst().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.tim3rst().clear_bit());
+    }
+}
+
+impl Instance for TIM4 {
+    const PTR: *const tim2::RegisterBlock = TIM4::PTR;
+    const NUMBER: u8 = 4;
+    const CLOCK_HZ: u32 = TIMCLK1_HZ;
+    const CHANNEL_PINS: [(Port, u8); 4] = [(Port::B, 6), (Port::B, 7), (Port::B, 8), (Port::B, 9)];
+    const ETR_PIN: (Port, u8) = (Port::E, 0);
+
+    fn enable_clock(rcc: &RCC) {
+        rcc.apb1enr.modify(|_, w| w.tim4en().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.tim4rst().set_bit());
+        rcc.apb1rstr.modify(|_, w| w.tim4rst().clear_bit());
+    }
+}
+
+/// 定时器
+/// 持有定时器外设的所有权，可以转换为 PWM 等工作模式
+pub struct Timer<TIM> {
+    tim: TIM,
+}
+
+impl<TIM: Instance> Timer<TIM> {
+    /// 使能定时器时钟并复位寄存器
+    pub fn new(tim: TIM, rcc: &RCC) -> Self {
+        TIM::enable_clock(rcc);
+        Timer { tim }
+    }
+
+    /// 释放定时器外设
+    pub fn release(self) -> TIM {
+        self.tim
+    }
+
+    /// 计数器输入时钟频率
+    pub fn clock_hz(&self) -> u32 {
+        TIM::CLOCK_HZ
+    }
+
+    /// 设置预分频值和自动重装载值，并产生更新事件立即生效
+    /// 计数频率 = 时钟频率 / (psc + 1)，溢出频率 = 计数频率 / (arr + 1)
+    pub fn set_period(&mut self, psc: u16, arr: u16) {
+        let tim = TIM::regs();
+        tim.psc.write(|w| w.psc().bits(psc));
+        tim.arr.write(|w| w.arr().bits(arr));
+        update(tim);
+    }
+
+    /// 按溢出频率设置预分频值和自动重装载值
+    pub fn set_frequency(&mut self, hz: u32) {
+        let (psc, arr) = compute_psc_arr(TIM::CLOCK_HZ, hz);
+        self.set_period(psc, arr);
+    }
+
+    /// 启动计数器
+    pub fn start(&mut self) {
+        TIM::regs().cr1.modify(|_, w| w.cen().set_bit());
+    }
+
+    /// 停止计数器
+    pub fn stop(&mut self) {
+        TIM::regs().cr1.modify(|_, w| w.cen().clear_bit());
+    }
+
+    /// 当前计数值
+    pub fn counter(&self) -> u16 {
+        TIM::regs().cnt.read().cnt().bits()
+    }
+}
+
+/// 根据时钟频率和目标频率计算预分频值和自动重装载值
+/// 自动重装载值在 1..=0xFFFE 之间，保证 `arr + 1` 能用 `u16` 表示；
+/// 目标频率超过 `clock_hz / 2` 时按 `clock_hz / 2` 计算
+pub fn compute_psc_arr(clock_hz: u32, hz: u32) -> (u16, u16) {
+    let ticks = (clock_hz / hz.max(1)).max(2);
+    let psc = ((ticks - 1) / 0xFFFF).min(0xFFFF);
+    let arr = (ticks / (psc + 1)).clamp(2, 0xFFFF) - 1;
+    (psc as u16, arr as u16)
+}
+
+/// SR 中的更新中断标志 UIF
+const SR_UIF: u32 = 1;
+
+/// 产生更新事件，把预装载的 PSC/ARR/CCR 写入影子寄存器
+fn update(tim: &tim2::RegisterBlock) {
+    tim.egr.write(|w| w.ug().set_bit());
+    // 软件更新事件也会置位更新中断标志，这里清除
+    clear_flags(tim, SR_UIF);
+}
+
+/// 清除 SR 中 `mask` 对应的标志
+/// SR 的标志写 0 清除、写 1 不变，直接写入而不是读-改-写，
+/// 避免把读取之后才置位的其它标志（如 CCxIF）一起清除
+fn clear_flags(tim: &tim2::RegisterBlock, mask: u32) {
+    tim.sr.write(|w| unsafe { w.bits(!mask) });
+}
+
+/// 把通道引脚配置为复用推挽输出
+fn configure_output_pin<TIM: Instance>(rcc: &RCC, channel: Channel) {
+    let (port, pin) = TIM::CHANNEL_PINS[channel.index()];
+    port.enable_clock(rcc);
+    rcc.apb2enr.modify(|_, w| w.afioen().set_bit());
+    set_pin_mode(port, pin, PinMode::AltPushPull);
+}
+
+/// 把通道引脚配置为输入
+fn configure_input_pin<TIM: Instance>(rcc: &RCC, channel: Channel, mode: PinMode) {
+    let (port, pin) = TIM::CHANNEL_PINS[channel.index()];
+    port.enable_clock(rcc);
+    set_pin_mode(port, pin, mode);
+}
+
+/// 把 ETR 引脚配置为输入
+fn configure_etr_pin<TIM: Instance>(rcc: &RCC, mode: PinMode) {
+    let (port, pin) = TIM::ETR_PIN;
+    port.enable_clock(rcc);
+    set_pin_mode(port, pin, mode);
+}
+
+/// 修改 CCMR1/CCMR2 中通道对应的 8 位配置
+/// 通道 1、2 在 CCMR1，通道 3、4 在 CCMR2，每个通道占 8 位
+fn set_ccmr(tim: &tim2::RegisterBlock, channel: Channel, mask: u32, value: u32) {
+    let shift = (channel.index() % 2) * 8;
+    let mask = mask << shift;
+    let value = value << shift;
+    if channel.index() < 2 {
+        tim.ccmr1_output()
+            .modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) });
+    } else {
+        tim.ccmr2_output()
+            .modify(|r, w| unsafe { w.bits((r.bits() & !mask) | value) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn psc_arr_for_common_rates() {
+        // 72MHz / 1kHz = 72000 个计数，超过 16 位需要 2 分频
+        assert_eq!(compute_psc_arr(72_000_000, 1_000), (1, 35_999));
+        assert_eq!(compute_psc_arr(72_000_000, 20_000), (0, 3_599));
+        assert_eq!(compute_psc_arr(72_000_000, 1), (1098, 65_513));
+    }
+
+    #[test]
+    fn arr_never_zero() {
+        assert_eq!(compute_psc_arr(72_000_000, 36_000_000), (0, 1));
+        assert_eq!(compute_psc_arr(72_000_000, 72_000_000), (0, 1));
+        assert_eq!(compute_psc_arr(72_000_000, 100_000_000), (0, 1));
+        assert_eq!(compute_psc_arr(72_000_000, 0), (1098, 65_513));
+    }
+}
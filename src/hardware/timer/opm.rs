@@ -0,0 +1,260 @@
+//! 单脉冲模式
+//!
+//! CR1 的 OPM 位置位后，计数器在下一个更新事件时自动停止，每次启动只输出一个脉冲。
+//! 输出通道工作在 PWM 模式 2：CNT < CCRx 时输出无效电平，之后输出有效电平，
+//! 计数到 ARR 后停止并回到无效电平，所以
+//! - 延时 = CCRx 个计数
+//! - 脉宽 = ARR + 1 - CCRx 个计数
+//!
+//! CCRx 为 0 时输出从计数开始就是有效电平，停止后也保持有效电平，
+//! 所以延时至少为 1 个计数。
+//!
+//! 启动方式可以是软件触发，也可以是通道 1/2 输入上的边沿（从模式为触发模式，
+//! 由硬件置位 CEN）。STM32F1 不支持硬件可重触发的单脉冲模式，
+//! 脉冲输出期间再次调用 `trigger` 会复位计数器，重新开始延时和脉冲。
+//!
+//! ```rust
+//! // PA6 输出 10us 的脉冲
+//! let mut pulse = Timer::new(dp.TIM3, rcc).one_pulse(rcc, Channel::C1, OnePulseConfig::default());
+//! pulse.set_pulse_us(1, 10)?;
+//! pulse.trigger();
+//! ```
+
+use stm32f1::stm32f103::RCC;
+
+use super::capture::{free_running_psc, CaptureEdge};
+use super::pwm::Polarity;
+use super::{
+    configure_input_pin, configure_output_pin, set_ccmr, update, Channel, Instance, Timer,
+};
+use crate::hardware::gpio::PinMode;
+
+/// 单脉冲的启动方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnePulseTrigger {
+    /// 调用 `trigger` 启动
+    Software,
+    /// 通道 1 引脚的边沿启动（TI1FP1）
+    Ti1(CaptureEdge),
+    /// 通道 2 引脚的边沿启动（TI2FP2）
+    Ti2(CaptureEdge),
+}
+
+/// 单脉冲配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OnePulseConfig {
+    /// 期望的计数频率，决定延时和脉宽的分辨率
+    /// 实际计数频率由 [`OnePulse::tick_hz`] 返回
+    pub tick_hz: u32,
+    /// 输出极性
+    pub polarity: Polarity,
+    /// 启动方式
+    pub trigger: OnePulseTrigger,
+    /// 触发输入的滤波器 ICxF（0~15）
+    pub filter: u8,
+}
+
+impl Default for OnePulseConfig {
+    fn default() -> Self {
+        OnePulseConfig {
+            tick_hz: 1_000_000,
+            polarity: Polarity::ActiveHigh,
+            trigger: OnePulseTrigger::Software,
+            filter: 0,
+        }
+    }
+}
+
+/// 单脉冲错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 延时为 0，PWM 模式 2 下输出会保持有效电平
+    ZeroDelay,
+    /// 脉宽为 0
+    ZeroWidth,
+    /// 延时与脉宽之和超过 65536 个计数
+    OutOfRange,
+}
+
+impl<TIM: Instance> Timer<TIM> {
+    /// 转换为单脉冲输出
+    /// 外部触发时，触发输入通道不能与输出通道相同
+    pub fn one_pulse(self, rcc: &RCC, output: Channel, config: OnePulseConfig) -> OnePulse<TIM> {
+        let tim = TIM::regs();
+
+        let psc = free_running_psc(TIM::CLOCK_HZ, config.tick_hz);
+        tim.psc.write(|w| w.psc().bits(psc as u16));
+        let tick_hz = TIM::CLOCK_HZ / (psc + 1);
+
+        // 触发输入
+        match config.trigger {
+            OnePulseTrigger::Software => {
+                tim.smcr.modify(|_, w| w.sms().disabled());
+            }
+            OnePulseTrigger::Ti1(edge) | OnePulseTrigger::Ti2(edge) => {
+                let input = match config.trigger {
+                    OnePulseTrigger::Ti1(_) => Channel::C1,
+                    _ => Channel::C2,
+                };
+                assert!(
+                    input != output,
+                    "trigger input and pulse output share a channel"
+                );
+
+                configure_input_pin::<TIM>(rcc, input, PinMode::FloatingInput);
+                // CCxS = 01 映射到 TIx，设置输入滤波器
+                let filter = (config.filter & 0b1111) as u32;
+                set_ccmr(tim, input, 0xFF, (filter << 4) | 0b01);
+                let falling = edge == CaptureEdge::Falling;
+                tim.ccer.modify(|_, w| match input {
+                    Channel::C1 => w.cc1e().clear_bit().cc1p().bit(falling),
+                    _ => w.cc2e().clear_bit().cc2p().bit(falling),
+                });
+
+                // 从模式：触发模式，触发时由硬件置位 CEN
+                tim.smcr.modify(|_, w| {
+                    match input {
+                        Channel::C1 => w.ts().ti1fp1(),
+                        _ => w.ts().ti2fp2(),
+                    };
+                    w.sms().trigger_mode()
+                });
+            }
+        }
+
+        // 输出通道：PWM 模式 2，开启 CCRx 预装载
+        configure_output_pin::<TIM>(rcc, output);
+        set_ccmr(tim, output, 0xFF, (0b111 << 4) | (1 << 3));
+        let index = output.index();
+        let polarity = 1 << (index * 4 + 1);
+        let enable = 1 << (index * 4);
+        tim.ccer.modify(|r, w| unsafe {
+            w.bits(match config.polarity {
+                Polarity::ActiveHigh => (r.bits() & !polarity) | enable,
+                Polarity::ActiveLow => r.bits() | polarity | enable,
+            })
+        });
+        TIM::enable_main_output();
+
+        // 单脉冲模式，只有计数器溢出才置位更新标志
+        tim.cr1
+            .modify(|_, w| w.opm().set_bit().arpe().set_bit().urs().set_bit());
+
+        let mut pulse = OnePulse {
+            timer: self,
+            output,
+            tick_hz,
+        };
+        // 默认：延时 1 个计数，1 个计数的脉冲
+        pulse.set_pulse(1, 1).unwrap();
+        pulse
+    }
+}
+
+/// 单脉冲输出
+pub struct OnePulse<TIM> {
+    timer: Timer<TIM>,
+    output: Channel,
+    tick_hz: u32,
+}
+
+impl<TIM: Instance> OnePulse<TIM> {
+    /// 停止输出并释放定时器
+    pub fn release(self) -> Timer<TIM> {
+        let tim = TIM::regs();
+        tim.cr1.modify(|_, w| w.cen().clear_bit().opm().clear_bit());
+        tim.smcr.reset();
+        tim.ccer.reset();
+        self.timer
+    }
+
+    /// 实际计数频率
+    pub fn tick_hz(&self) -> u32 {
+        self.tick_hz
+    }
+
+    /// 设置延时和脉宽（计数值）
+    /// 延时至少为 1 个计数；脉冲输出期间修改，在本次脉冲结束后生效
+    pub fn set_pulse(&mut self, delay_ticks: u32, width_ticks: u32) -> Result<(), Error> {
+        let (ccr, arr) = pulse_registers(delay_ticks, width_ticks)?;
+
+        let tim = TIM::regs();
+        tim.ccr[self.output.index()].write(|w| w.ccr().bits(ccr));
+        tim.arr.write(|w| w.arr().bits(arr));
+        if !self.is_busy() {
+            // 产生更新事件使 ARR/CCR 立即生效
+            update(tim);
+        }
+        Ok(())
+    }
+
+    /// 设置延时和脉宽（微秒）
+    /// 延时换算后不足 1 个计数时返回 `Error::ZeroDelay`
+    pub fn set_pulse_us(&mut self, delay_us: u32, width_us: u32) -> Result<(), Error> {
+        let delay = us_to_ticks(delay_us, self.tick_hz)?;
+        let width = us_to_ticks(width_us, self.tick_hz)?;
+        self.set_pulse(delay, width)
+    }
+
+    /// 软件启动一个脉冲
+    /// 脉冲输出期间调用会复位计数器，从延时阶段重新开始
+    pub fn trigger(&mut self) {
+        let tim = TIM::regs();
+        if self.is_busy() {
+            // 更新事件使计数器清零，单脉冲模式下同时清除 CEN
+            update(tim);
+        }
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+    }
+
+    /// 是否正在输出脉冲（含延时阶段）
+    pub fn is_busy(&self) -> bool {
+        TIM::regs().cr1.read().cen().bit_is_set()
+    }
+
+    /// 阻塞等待当前脉冲结束
+    pub fn wait(&self) {
+        while self.is_busy() {}
+    }
+}
+
+/// 延时和脉宽对应的 CCRx 和 ARR
+fn pulse_registers(delay_ticks: u32, width_ticks: u32) -> Result<(u16, u16), Error> {
+    if delay_ticks == 0 {
+        return Err(Error::ZeroDelay);
+    }
+    if width_ticks == 0 {
+        return Err(Error::ZeroWidth);
+    }
+    let end = delay_ticks
+        .checked_add(width_ticks)
+        .filter(|end| *end <= 0x1_0000)
+        .ok_or(Error::OutOfRange)?;
+    Ok((delay_ticks as u16, (end - 1) as u16))
+}
+
+fn us_to_ticks(us: u32, tick_hz: u32) -> Result<u32, Error> {
+    let ticks = us as u64 * tick_hz as u64 / 1_000_000;
+    u32::try_from(ticks).map_err(|_| Error::OutOfRange)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pulse_register_values() {
+        assert_eq!(pulse_registers(1, 1), Ok((1, 1)));
+        assert_eq!(pulse_registers(1, 10), Ok((1, 10)));
+        assert_eq!(pulse_registers(100, 50), Ok((100, 149)));
+        assert_eq!(pulse_registers(1, 0xFFFF), Ok((1, 0xFFFF)));
+    }
+
+    #[test]
+    fn rejects_invalid_pulses() {
+        assert_eq!(pulse_registers(0, 10), Err(Error::ZeroDelay));
+        assert_eq!(pulse_registers(10, 0), Err(Error::ZeroWidth));
+        assert_eq!(pulse_registers(2, 0xFFFF), Err(Error::OutOfRange));
+        assert_eq!(pulse_registers(u32::MAX, 1), Err(Error::OutOfRange));
+    }
+}
@@ -0,0 +1,186 @@
+//! PWM 输出
+//!
+//! 输出比较 PWM 模式：
+//! - 模式 1：向上计数时，CNT < CCRx 输出有效电平，否则输出无效电平
+//! - 模式 2：与模式 1 相反
+//!
+//! 占空比 = CCRx / (ARR + 1)，CCRx 与 ARR 均开启预装载，在下一个更新事件时生效。
+
+use core::convert::Infallible;
+use core::marker::PhantomData;
+
+use embedded_hal::pwm::{ErrorType, SetDutyCycle};
+use stm32f1::stm32f103::{tim2, RCC};
+
+use super::{compute_psc_arr, configure_output_pin, set_ccmr, update, Channel, Instance, Timer};
+
+/// PWM 模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PwmMode {
+    /// PWM 模式 1
+    Mode1,
+    /// PWM 模式 2
+    Mode2,
+}
+
+/// 输出极性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Polarity {
+    /// 高电平有效
+    ActiveHigh,
+    /// 低电平有效
+    ActiveLow,
+}
+
+impl<TIM: Instance> Timer<TIM> {
+    /// 转换为 PWM 输出
+    /// 配置 `channels` 的引脚为复用推挽输出，通道默认为 PWM 模式 1、高电平有效、占空比为 0
+    pub fn pwm(self, rcc: &RCC, channels: &[Channel], hz: u32) -> Pwm<TIM> {
+        let tim = TIM::regs();
+
+        // 自动重装载预装载使能
+        tim.cr1.modify(|_, w| w.arpe().set_bit());
+
+        let mut pwm = Pwm { timer: self };
+        pwm.set_frequency(hz);
+
+        for &channel in channels {
+            configure_output_pin::<TIM>(rcc, channel);
+            pwm.set_mode(channel, PwmMode::Mode1);
+            pwm.set_polarity(channel, Polarity::ActiveHigh);
+            pwm.set_duty(channel, 0);
+            pwm.enable(channel);
+        }
+
+        // 高级控制定时器需要使能主输出
+        TIM::enable_main_output();
+        // 启动计数器
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        pwm
+    }
+}
+
+/// PWM 输出
+pub struct Pwm<TIM> {
+    timer: Timer<TIM>,
+}
+
+impl<TIM: Instance> Pwm<TIM> {
+    /// 停止 PWM 输出并释放定时器
+    pub fn release(self) -> Timer<TIM> {
+        let tim = TIM::regs();
+        tim.cr1.modify(|_, w| w.cen().clear_bit());
+        tim.ccer.reset();
+        self.timer
+    }
+
+    /// 设置 PWM 频率
+    /// 会改变 ARR，已设置的占空比需要按新的最大值重新设置
+    pub fn set_frequency(&mut self, hz: u32) {
+        let (psc, arr) = compute_psc_arr(TIM::CLOCK_HZ, hz);
+        let tim = TIM::regs();
+        tim.psc.write(|w| w.psc().bits(psc));
+        tim.arr.write(|w| w.arr().bits(arr));
+        update(tim);
+    }
+
+    /// 当前 PWM 频率
+    pub fn get_frequency(&self) -> u32 {
+        let tim = TIM::regs();
+        let psc = tim.psc.read().psc().bits() as u32;
+        let arr = tim.arr.read().arr().bits() as u32;
+        TIM::CLOCK_HZ / ((psc + 1) * (arr + 1))
+    }
+
+    /// 占空比的最大值，即 ARR + 1，对应 100% 占空比
+    pub fn get_max_duty(&self) -> u16 {
+        max_duty(TIM::regs())
+    }
+
+    /// 设置占空比
+    pub fn set_duty(&mut self, channel: Channel, duty: u16) {
+        set_duty(TIM::regs(), channel, duty);
+    }
+
+    /// 当前占空比
+    pub fn get_duty(&self, channel: Channel) -> u16 {
+        TIM::regs().ccr[channel.index()].read().ccr().bits()
+    }
+
+    /// 设置 PWM 模式，并开启 CCRx 预装载
+    pub fn set_mode(&mut self, channel: Channel, mode: PwmMode) {
+        let ocm = match mode {
+            PwmMode::Mode1 => 0b110,
+            PwmMode::Mode2 => 0b111,
+        };
+        // CCxS = 00（输出），OCxPE = 1（预装载），OCxM = 模式
+        set_ccmr(TIM::regs(), channel, 0b1111_1011, (ocm << 4) | (1 << 3));
+    }
+
+    /// 设置输出极性
+    pub fn set_polarity(&mut self, channel: Channel, polarity: Polarity) {
+        let mask = 1 << (channel.index() * 4 + 1);
+        TIM::regs().ccer.modify(|r, w| unsafe {
+            w.bits(match polarity {
+                Polarity::ActiveHigh => r.bits() & !mask,
+                Polarity::ActiveLow => r.bits() | mask,
+            })
+        });
+    }
+
+    /// 使能通道输出
+    pub fn enable(&mut self, channel: Channel) {
+        let mask = 1 << (channel.index() * 4);
+        TIM::regs()
+            .ccer
+            .modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+    }
+
+    /// 关闭通道输出
+    pub fn disable(&mut self, channel: Channel) {
+        let mask = 1 << (channel.index() * 4);
+        TIM::regs()
+            .ccer
+            .modify(|r, w| unsafe { w.bits(r.bits() & !mask) });
+    }
+
+    /// 获取单个通道，可作为 `embedded_hal::pwm::SetDutyCycle` 使用
+    pub fn channel(&mut self, channel: Channel) -> PwmChannel<'_, TIM> {
+        PwmChannel {
+            channel,
+            _pwm: PhantomData,
+        }
+    }
+}
+
+/// PWM 通道
+pub struct PwmChannel<'a, TIM> {
+    channel: Channel,
+    _pwm: PhantomData<&'a mut Pwm<TIM>>,
+}
+
+impl<'a, TIM: Instance> ErrorType for PwmChannel<'a, TIM> {
+    type Error = Infallible;
+}
+
+impl<'a, TIM: Instance> SetDutyCycle for PwmChannel<'a, TIM> {
+    fn max_duty_cycle(&self) -> u16 {
+        max_duty(TIM::regs())
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        set_duty(TIM::regs(), self.channel, duty);
+        Ok(())
+    }
+}
+
+/// ARR 为 0xFFFF 时 `arr + 1` 超出 `u16`，取饱和值
+fn max_duty(tim: &tim2::RegisterBlock) -> u16 {
+    tim.arr.read().arr().bits().saturating_add(1)
+}
+
+fn set_duty(tim: &tim2::RegisterBlock, channel: Channel, duty: u16) {
+    let duty = duty.min(max_duty(tim));
+    tim.ccr[channel.index()].write(|w| w.ccr().bits(duty));
+}
@@ -0,0 +1,187 @@
+//! 正交编码器接口
+//!
+//! 定时器的通道 1、2 接编码器的 A、B 相，从模式选择编码器模式后，
+//! 计数器根据两相的相位关系自动加减计数，计数方向由 CR1 的 DIR 位指示。
+//!
+//! | 模式        | SMS   | 计数边沿             | 每个周期计数 |
+//! |-------------|-------|----------------------|--------------|
+//! | `Ti1`       | 0b001 | TI1 的边沿           | 2            |
+//! | `Ti2`       | 0b010 | TI2 的边沿           | 2            |
+//! | `Ti1AndTi2` | 0b011 | TI1 和 TI2 的边沿    | 4            |
+//!
+//! 硬件计数器只有 16 位，`position` 根据两次读取之间的差值扩展为 32 位位置，
+//! 两次调用之间的转动不能超过 32767 个计数。
+//!
+//! ```rust
+//! // PA6、PA7 接旋转编码器
+//! let mut qei = Timer::new(dp.TIM3, rcc).qei(rcc, QeiConfig::default());
+//! let position = qei.position();
+//! ```
+
+use stm32f1::stm32f103::RCC;
+
+use super::{clear_flags, configure_input_pin, set_ccmr, Channel, Instance, Timer, SR_UIF};
+use crate::hardware::gpio::PinMode;
+
+/// 编码器模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncoderMode {
+    /// 编码器模式 1，只在 TI1 的边沿计数
+    Ti1,
+    /// 编码器模式 2，只在 TI2 的边沿计数
+    Ti2,
+    /// 编码器模式 3，在 TI1 和 TI2 的边沿都计数（4 倍频）
+    Ti1AndTi2,
+}
+
+/// 计数方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// 向上计数
+    Up,
+    /// 向下计数
+    Down,
+}
+
+/// 编码器配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QeiConfig {
+    /// 编码器模式
+    pub mode: EncoderMode,
+    /// 输入滤波器 ICxF（0~15），机械编码器需要较强的滤波
+    pub filter: u8,
+    /// 反相 A 相（TI1）输入，交换计数方向
+    pub invert_a: bool,
+    /// 反相 B 相（TI2）输入，交换计数方向
+    pub invert_b: bool,
+    /// 引脚输入模式，开漏输出的编码器需要上拉
+    pub pin_mode: PinMode,
+}
+
+impl Default for QeiConfig {
+    fn default() -> Self {
+        QeiConfig {
+            mode: EncoderMode::Ti1AndTi2,
+            filter: 0b0110,
+            invert_a: false,
+            invert_b: false,
+            pin_mode: PinMode::PullUpInput,
+        }
+    }
+}
+
+impl<TIM: Instance> Timer<TIM> {
+    /// 转换为正交编码器接口
+    /// A 相接通道 1 引脚，B 相接通道 2 引脚
+    pub fn qei(self, rcc: &RCC, config: QeiConfig) -> Qei<TIM> {
+        let tim = TIM::regs();
+
+        configure_input_pin::<TIM>(rcc, Channel::C1, config.pin_mode);
+        configure_input_pin::<TIM>(rcc, Channel::C2, config.pin_mode);
+
+        // CCxS = 01，IC1 映射到 TI1，IC2 映射到 TI2
+        let filter = (config.filter & 0b1111) as u32;
+        set_ccmr(tim, Channel::C1, 0xFF, (filter << 4) | 0b01);
+        set_ccmr(tim, Channel::C2, 0xFF, (filter << 4) | 0b01);
+
+        // 编码器模式下 CCxP 控制输入是否反相，不需要使能捕获
+        tim.ccer.modify(|_, w| {
+            w.cc1p()
+                .bit(config.invert_a)
+                .cc2p()
+                .bit(config.invert_b)
+                .cc1e()
+                .clear_bit()
+                .cc2e()
+                .clear_bit()
+        });
+
+        tim.smcr.modify(|_, w| match config.mode {
+            EncoderMode::Ti1 => w.sms().encoder_mode_1(),
+            EncoderMode::Ti2 => w.sms().encoder_mode_2(),
+            EncoderMode::Ti1AndTi2 => w.sms().encoder_mode_3(),
+        });
+
+        // 计数器在 0~0xFFFF 之间循环
+        tim.psc.write(|w| w.psc().bits(0));
+        tim.arr.write(|w| w.arr().bits(0xFFFF));
+        tim.egr.write(|w| w.ug().set_bit());
+        clear_flags(tim, SR_UIF);
+        tim.cnt.write(|w| w.cnt().bits(0));
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        Qei {
+            timer: self,
+            last_count: 0,
+            position: 0,
+        }
+    }
+}
+
+/// 正交编码器接口
+pub struct Qei<TIM> {
+    timer: Timer<TIM>,
+    /// 上次读取的计数值
+    last_count: u16,
+    /// 扩展后的位置
+    position: i32,
+}
+
+impl<TIM: Instance> Qei<TIM> {
+    /// 停止计数并释放定时器
+    pub fn release(self) -> Timer<TIM> {
+        let tim = TIM::regs();
+        tim.cr1.modify(|_, w| w.cen().clear_bit());
+        tim.smcr.modify(|_, w| w.sms().disabled());
+        tim.ccer.reset();
+        self.timer
+    }
+
+    /// 硬件计数器的原始值
+    pub fn count(&self) -> u16 {
+        TIM::regs().cnt.read().cnt().bits()
+    }
+
+    /// 以有符号数表示的计数值，从 0 反转时为负数
+    pub fn signed_count(&self) -> i16 {
+        self.count() as i16
+    }
+
+    /// 当前计数方向，即最近一次计数时的转动方向
+    pub fn direction(&self) -> Direction {
+        if TIM::regs().cr1.read().dir().bit_is_set() {
+            Direction::Down
+        } else {
+            Direction::Up
+        }
+    }
+
+    /// 扩展到 32 位的位置
+    /// 按最近两次读数的差值累加，需要在计数变化 32767 之前再次调用
+    pub fn position(&mut self) -> i32 {
+        let count = self.count();
+        let delta = count.wrapping_sub(self.last_count) as i16;
+        self.last_count = count;
+        self.position = self.position.wrapping_add(delta as i32);
+        self.position
+    }
+
+    /// 自上次调用以来的变化量，可用于计算转速
+    pub fn delta(&mut self) -> i32 {
+        let previous = self.position;
+        self.position().wrapping_sub(previous)
+    }
+
+    /// 设置当前位置
+    pub fn set_position(&mut self, position: i32) {
+        self.last_count = self.count();
+        self.position = position;
+    }
+
+    /// 计数器和位置清零
+    pub fn reset(&mut self) {
+        TIM::regs().cnt.write(|w| w.cnt().bits(0));
+        self.last_count = 0;
+        self.position = 0;
+    }
+}
@@ -0,0 +1,297 @@
+//! 窗口看门狗（WWDG）
+//!
+//! 由 PCLK1 驱动的 7 位递减计数器 T[6:0]，计数时钟为 PCLK1 / 4096 / 2^WDGTB：
+//! - T 从 0x40 减到 0x3F（T6 清零）时复位，因此计数器取值范围为 0x40~0x7F
+//! - T 大于窗口值 W 时刷新计数器同样会复位，用于发现跑得过快的循环
+//! - T 减到 0x40 时产生提前唤醒中断（EWI），只剩一个计数周期，可用于记录故障现场
+//!
+//! PCLK1 为 36MHz 时，计数周期约为 114us（1 分频）~910us（8 分频），
+//! 超时时间范围约为 0.11ms~58ms。
+//!
+//! ```rust
+//! let mut watchdog = WindowWatchdog::new(dp.WWDG, rcc);
+//! // 刷新后 20ms 内不允许再刷新，50ms 内必须刷新
+//! watchdog.start(50_000, 20_000).unwrap();
+//! loop {
+//!     delay_ms(&mut syst, 30);
+//!     watchdog.refresh().unwrap();
+//! }
+//! ```
+
+use stm32f1::stm32f103::{DBGMCU, RCC, WWDG};
+
+use super::cfgr::PCLK1_HZ;
+
+/// 计数器的最小值，T6 为 1
+pub const COUNTER_MIN: u8 = 0x40;
+/// 计数器的最大值
+pub const COUNTER_MAX: u8 = 0x7F;
+/// 固定的 4096 分频
+const BASE_DIVIDER: u64 = 4096;
+/// WDGTB 的最大值，对应 8 分频
+const MAX_PRESCALER: u8 = 3;
+
+/// 看门狗错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 超时时间小于一个计数周期
+    TimeoutTooShort,
+    /// 超时时间超过 8 分频时的最大值
+    TimeoutTooLong,
+    /// 窗口时间不小于超时时间，永远无法刷新
+    InvalidWindow,
+    /// 计数器仍大于窗口值，此时刷新会复位，已拒绝
+    OutsideWindow,
+}
+
+/// 窗口看门狗配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WwdgConfig {
+    /// 预分频 WDGTB，0~3 对应 1、2、4、8 分频
+    pub prescaler: u8,
+    /// 刷新时写入的计数值 T
+    pub counter: u8,
+    /// 窗口值 W，计数器不大于该值时才允许刷新
+    pub window: u8,
+}
+
+impl WwdgConfig {
+    /// 由超时时间和窗口时间（微秒）计算配置
+    /// - `timeout_us`: 刷新后到复位的最长时间，结果向下取整
+    /// - `window_us`: 刷新后到允许再次刷新的最短时间，结果向上取整，为 0 时不限制
+    ///
+    /// 选择能表示该超时的最小分频，分辨率最高
+    pub fn from_timing(pclk1_hz: u32, timeout_us: u32, window_us: u32) -> Result<Self, Error> {
+        let range = (COUNTER_MAX - COUNTER_MIN + 1) as u64;
+        for prescaler in 0..=MAX_PRESCALER {
+            let tick_div = BASE_DIVIDER << prescaler;
+            // 一个计数周期为 tick_div / pclk1_hz 秒
+            let ticks = timeout_us as u64 * pclk1_hz as u64 / (1_000_000 * tick_div);
+            if ticks > range {
+                continue;
+            }
+            if ticks == 0 {
+                return Err(Error::TimeoutTooShort);
+            }
+            // 计数器减到 0x3F 时复位
+            let counter = (COUNTER_MIN as u64 - 1 + ticks) as u8;
+
+            let per_tick = 1_000_000 * tick_div;
+            let window_ticks = (window_us as u64 * pclk1_hz as u64).div_ceil(per_tick);
+            if window_ticks >= ticks {
+                return Err(Error::InvalidWindow);
+            }
+            // 刷新后经过 window_ticks 个周期，计数器减到窗口值
+            let window = if window_ticks == 0 {
+                COUNTER_MAX
+            } else {
+                counter - window_ticks as u8
+            };
+
+            return Ok(WwdgConfig {
+                prescaler,
+                counter,
+                window,
+            });
+        }
+        Err(Error::TimeoutTooLong)
+    }
+
+    /// 一个计数周期（纳秒）
+    pub fn tick_ns(&self, pclk1_hz: u32) -> u32 {
+        ((BASE_DIVIDER << self.prescaler) * 1_000_000_000 / pclk1_hz as u64) as u32
+    }
+
+    /// 刷新后到复位的时间（微秒）
+    pub fn timeout_us(&self, pclk1_hz: u32) -> u32 {
+        let ticks = (self.counter - COUNTER_MIN + 1) as u32;
+        ticks * self.tick_ns(pclk1_hz) / 1000
+    }
+
+    /// 刷新后到允许再次刷新的时间（微秒）
+    pub fn window_us(&self, pclk1_hz: u32) -> u32 {
+        let ticks = self.counter.saturating_sub(self.window) as u32;
+        ticks * self.tick_ns(pclk1_hz) / 1000
+    }
+}
+
+/// 窗口看门狗
+pub struct WindowWatchdog {
+    wwdg: WWDG,
+    config: WwdgConfig,
+}
+
+impl WindowWatchdog {
+    /// 使能 APB1 WWDG 时钟
+    pub fn new(wwdg: WWDG, rcc: &RCC) -> Self {
+        rcc.apb1enr.modify(|_, w| w.wwdgen().enabled());
+        WindowWatchdog {
+            wwdg,
+            config: WwdgConfig {
+                prescaler: 0,
+                counter: COUNTER_MAX,
+                window: COUNTER_MAX,
+            },
+        }
+    }
+
+    /// 调试时（内核停止）是否冻结看门狗计数
+    pub fn stop_on_debug(&self, dbgmcu: &DBGMCU, stop: bool) {
+        dbgmcu.cr.modify(|_, w| w.dbg_wwdg_stop().bit(stop));
+    }
+
+    /// 按 `set_clock` 配置的 PCLK1 计算超时时间和窗口时间（微秒）并启动看门狗
+    /// 启动后只能由复位停止
+    pub fn start(&mut self, timeout_us: u32, window_us: u32) -> Result<WwdgConfig, Error> {
+        let config = WwdgConfig::from_timing(PCLK1_HZ, timeout_us, window_us)?;
+        self.start_with(config);
+        Ok(config)
+    }
+
+    /// 按给定配置启动看门狗
+    pub fn start_with(&mut self, config: WwdgConfig) {
+        self.config = config;
+        self.wwdg.cfr.modify(|_, w| {
+            w.wdgtb().bits(config.prescaler);
+            w.w().bits(config.window)
+        });
+        // 写入计数器的同时使能看门狗，T6 为 1，不会立即复位
+        self.wwdg
+            .cr
+            .write(|w| w.t().bits(config.counter).wdga().enabled());
+    }
+
+    /// 当前配置
+    pub fn config(&self) -> WwdgConfig {
+        self.config
+    }
+
+    /// 当前计数值
+    pub fn counter(&self) -> u8 {
+        self.wwdg.cr.read().t().bits()
+    }
+
+    /// 当前是否处于允许刷新的窗口内
+    pub fn is_in_window(&self) -> bool {
+        self.counter() <= self.wwdg.cfr.read().w().bits()
+    }
+
+    /// 刷新计数器
+    /// 还未进入窗口时拒绝刷新并返回 [`Error::OutsideWindow`]，由调用者决定如何处理
+    pub fn refresh(&mut self) -> Result<(), Error> {
+        if !self.is_in_window() {
+            return Err(Error::OutsideWindow);
+        }
+        self.wwdg.cr.write(|w| w.t().bits(self.config.counter));
+        Ok(())
+    }
+
+    /// 使能提前唤醒中断，计数器减到 0x40 时触发 WWDG 中断
+    /// 使能后只能由复位关闭
+    pub fn listen_early_wakeup(&mut self) {
+        self.wwdg.cfr.modify(|_, w| w.ewi().enable());
+    }
+
+    /// 是否触发了提前唤醒中断
+    pub fn is_early_wakeup(&self) -> bool {
+        self.wwdg.sr.read().ewif().is_pending()
+    }
+
+    /// 清除提前唤醒中断标志
+    pub fn clear_early_wakeup(&mut self) {
+        self.wwdg.sr.write(|w| w.ewif().finished());
+    }
+}
+
+/// 上次复位是否由窗口看门狗引起
+pub fn is_reset_cause(rcc: &RCC) -> bool {
+    rcc.csr.read().wwdgrstf().bit_is_set()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(prescaler: u8, counter: u8, window: u8) -> WwdgConfig {
+        WwdgConfig {
+            prescaler,
+            counter,
+            window,
+        }
+    }
+
+    #[test]
+    fn timeout_and_window() {
+        // 8 分频，计数周期约 910us：超时 54 个周期，窗口 22 个周期
+        let wwdg = WwdgConfig::from_timing(PCLK1_HZ, 50_000, 20_000).unwrap();
+        assert_eq!(wwdg, config(3, 0x75, 0x5F));
+        assert_eq!(wwdg.tick_ns(PCLK1_HZ), 910_222);
+        assert_eq!(wwdg.timeout_us(PCLK1_HZ), 49_151);
+        assert_eq!(wwdg.window_us(PCLK1_HZ), 20_024);
+        // 窗口为 0 时不限制
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 50_000, 0),
+            Ok(config(3, 0x75, COUNTER_MAX))
+        );
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 1_000, 500),
+            Ok(config(0, 0x47, 0x42))
+        );
+    }
+
+    #[test]
+    fn prescaler_steps() {
+        // 1 分频最多 64 个周期，约 7.28ms
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 7_395, 0),
+            Ok(config(0, COUNTER_MAX, COUNTER_MAX))
+        );
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 7_396, 0),
+            Ok(config(1, 0x5F, COUNTER_MAX))
+        );
+    }
+
+    #[test]
+    fn timeout_out_of_range() {
+        // 1 分频一个周期约 113.8us
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 0, 0),
+            Err(Error::TimeoutTooShort)
+        );
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 113, 0),
+            Err(Error::TimeoutTooShort)
+        );
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 114, 0),
+            Ok(config(0, COUNTER_MIN, COUNTER_MAX))
+        );
+        // 8 分频 64 个周期约 58.25ms，向下取整到 59.16ms 仍为 64 个周期
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 59_164, 0),
+            Ok(config(3, COUNTER_MAX, COUNTER_MAX))
+        );
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 59_165, 0),
+            Err(Error::TimeoutTooLong)
+        );
+    }
+
+    #[test]
+    fn invalid_window() {
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 50_000, 50_000),
+            Err(Error::InvalidWindow)
+        );
+        // 向上取整后与超时周期数相同
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 50_000, 49_000),
+            Err(Error::InvalidWindow)
+        );
+        assert_eq!(
+            WwdgConfig::from_timing(PCLK1_HZ, 50_000, 48_000),
+            Ok(config(3, 0x75, COUNTER_MIN))
+        );
+    }
+}
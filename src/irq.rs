@@ -0,0 +1,276 @@
+//! 中断共享资源
+//!
+//! 取代各示例中手写的 `Mutex<RefCell<Option<T>>>` 与 `static mut COUNT`：
+//! - `IrqShared<T>`：主程序把外设或驱动一次性移交给指定的中断，中断处理函数中无锁地获得 `&mut T`
+//! - `IrqCounter`、`IrqFlag`：中断与主程序之间通信用的原子计数器和标志
+//!
+//! ```rust
+//! static G_TIM2: IrqShared<TIM2> = IrqShared::new();
+//! static COUNT: IrqCounter = IrqCounter::new();
+//!
+//! // 主程序中移交给 TIM2 中断，之后再使能中断
+//! G_TIM2.init(Interrupt::TIM2, tim2);
+//!
+//! #[interrupt]
+//! fn TIM2() {
+//!     G_TIM2.with(|tim2| {
+//!         tim2.sr.modify(|_, w| w.uif().clear_bit());
+//!         COUNT.increment();
+//!     });
+//! }
+//!
+//! // 主程序中读取计数
+//! let count = COUNT.get();
+//! ```
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU8, Ordering};
+
+use cortex_m::interrupt::InterruptNumber;
+use cortex_m::peripheral::{scb::Exception, SCB};
+
+/// 未初始化
+const EMPTY: u8 = 0;
+/// 正在写入
+const WRITING: u8 = 1;
+/// 已移交，可以访问
+const READY: u8 = 2;
+/// 中断处理函数正在访问
+const BORROWED: u8 = 3;
+
+/// 移交给某个中断独占使用的资源
+///
+/// 资源只能被移交一次，移交后只能在所属中断（或异常）的处理函数中通过 `with` 访问。
+/// 同一个中断不会抢占自身，所以处理函数中获得的 `&mut T` 是独占的，不需要临界区。
+pub struct IrqShared<T> {
+    state: AtomicU8,
+    /// 所属中断的异常号（ICSR.VECTACTIVE 的值）
+    vector: AtomicU16,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// 资源只会在所属中断中被访问，可以在线程与中断之间转移
+unsafe impl<T: Send> Sync for IrqShared<T> {}
+
+impl<T> Default for IrqShared<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IrqShared<T> {
+    /// 创建一个空的共享资源，用于 `static` 初始化
+    pub const fn new() -> Self {
+        IrqShared {
+            state: AtomicU8::new(EMPTY),
+            vector: AtomicU16::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// 把资源移交给中断 `irq`
+    /// 重复移交时 panic，应在使能该中断之前调用
+    pub fn init<I: InterruptNumber>(&self, irq: I, value: T) {
+        if self.try_init(irq, value).is_err() {
+            panic!("IrqShared: resource already handed over");
+        }
+    }
+
+    /// 把资源移交给中断 `irq`，已经移交过时把资源原样返回
+    pub fn try_init<I: InterruptNumber>(&self, irq: I, value: T) -> Result<(), T> {
+        self.hand_over(irq.number() + 16, value)
+    }
+
+    /// 把资源移交给内核异常（如 `SysTick`）
+    /// 重复移交时 panic
+    pub fn init_exception(&self, exception: Exception, value: T) {
+        let vector = (exception.irqn() as i16 + 16) as u16;
+        if self.hand_over(vector, value).is_err() {
+            panic!("IrqShared: resource already handed over");
+        }
+    }
+
+    /// 资源是否已经移交
+    pub fn is_initialized(&self) -> bool {
+        self.state.load(Ordering::Acquire) >= READY
+    }
+
+    /// 在所属中断的处理函数中访问资源
+    ///
+    /// 资源尚未移交（包括正在移交）时返回 `None`；在其它上下文中调用会 panic；
+    /// 在闭包内嵌套访问同一资源时返回 `None`。
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        // 移交完成之前 vector 可能尚未写入，不能用于检查
+        let state = self.state.load(Ordering::Acquire);
+        if state != READY && state != BORROWED {
+            return None;
+        }
+        if current_vector() != self.vector.load(Ordering::Relaxed) {
+            panic!("IrqShared: accessed outside of the owning interrupt");
+        }
+        if self
+            .state
+            .compare_exchange(READY, BORROWED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return None;
+        }
+
+        // 状态为 BORROWED 期间只有当前处理函数持有引用
+        let value = unsafe { (*self.value.get()).assume_init_mut() };
+        let result = f(value);
+
+        self.state.store(READY, Ordering::Release);
+        Some(result)
+    }
+
+    fn hand_over(&self, vector: u16, value: T) -> Result<(), T> {
+        if self
+            .state
+            .compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(value);
+        }
+
+        unsafe { (*self.value.get()).write(value) };
+        self.vector.store(vector, Ordering::Relaxed);
+        self.state.store(READY, Ordering::Release);
+        Ok(())
+    }
+}
+
+/// 当前正在执行的异常号，线程模式下为 0
+pub(crate) fn current_vector() -> u16 {
+    // 只读访问 ICSR 寄存器
+    let icsr = unsafe { (*SCB::PTR).icsr.read() };
+    (icsr & 0x1FF) as u16
+}
+
+/// 中断与主程序之间共享的计数器
+pub struct IrqCounter(AtomicU32);
+
+impl Default for IrqCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IrqCounter {
+    /// 创建计数器，初始值为 0
+    pub const fn new() -> Self {
+        IrqCounter(AtomicU32::new(0))
+    }
+
+    /// 计数加 1
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    /// 计数加 `n`
+    pub fn add(&self, n: u32) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// 读取当前计数
+    pub fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// 读取当前计数并清零
+    pub fn take(&self) -> u32 {
+        self.0.swap(0, Ordering::Relaxed)
+    }
+
+    /// 设置计数
+    pub fn set(&self, value: u32) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
+
+/// 中断与主程序之间共享的标志
+pub struct IrqFlag(AtomicBool);
+
+impl Default for IrqFlag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IrqFlag {
+    /// 创建标志，初始为未置位
+    pub const fn new() -> Self {
+        IrqFlag(AtomicBool::new(false))
+    }
+
+    /// 置位
+    pub fn set(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// 清除
+    pub fn clear(&self) {
+        self.0.store(false, Ordering::Release);
+    }
+
+    /// 是否已置位
+    pub fn is_set(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// 读取并清除标志
+    pub fn take(&self) -> bool {
+        self.0.swap(false, Ordering::AcqRel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_not_ready() {
+        let shared: IrqShared<u32> = IrqShared::new();
+        assert!(!shared.is_initialized());
+        assert_eq!(shared.with(|value| *value), None);
+
+        // 移交过程中被中断：vector 尚未写入，返回 None 而不是 panic
+        shared.state.store(WRITING, Ordering::Relaxed);
+        assert!(!shared.is_initialized());
+        assert_eq!(shared.with(|value| *value), None);
+    }
+
+    #[test]
+    fn counter() {
+        let counter = IrqCounter::new();
+        assert_eq!(counter.get(), 0);
+        counter.increment();
+        counter.add(4);
+        assert_eq!(counter.get(), 5);
+        assert_eq!(counter.take(), 5);
+        assert_eq!(counter.get(), 0);
+
+        // 溢出时回绕
+        counter.set(u32::MAX);
+        counter.increment();
+        assert_eq!(counter.get(), 0);
+    }
+
+    #[test]
+    fn flag() {
+        let flag = IrqFlag::default();
+        assert!(!flag.is_set());
+        assert!(!flag.take());
+
+        flag.set();
+        flag.set();
+        assert!(flag.is_set());
+        assert!(flag.take());
+        assert!(!flag.is_set());
+
+        flag.set();
+        flag.clear();
+        assert!(!flag.take());
+    }
+}
@@ -1,3 +1,7 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
+pub mod calendar;
+pub mod executor;
 pub mod hardware;
+pub mod irq;
+pub mod soft_timer;
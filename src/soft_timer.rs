@@ -0,0 +1,451 @@
+//! 软件定时器
+//!
+//! 在一个硬件节拍（SysTick 或 TIMx 更新中断）上驱动多个相互独立的软件定时器，
+//! 支持单次/周期两种模式、回调函数或到期标志、取消与重新调度，不使用堆分配。
+//!
+//! ```rust
+//! static TIMERS: Mutex<RefCell<SoftTimers<4>>> = Mutex::new(RefCell::new(SoftTimers::new()));
+//!
+//! // 主程序中注册一个 500 个节拍的周期定时器
+//! let id = cortex_m::interrupt::free(|cs| {
+//!     TIMERS.borrow(cs).borrow_mut().start(Mode::Periodic, 500, Some(toggle_led))
+//! });
+//!
+//! // 在节拍中断中推进定时器，释放借用后再执行到期的回调
+//! #[exception]
+//! fn SysTick() {
+//!     let expired = cortex_m::interrupt::free(|cs| TIMERS.borrow(cs).borrow_mut().tick());
+//!     expired.run();
+//! }
+//! ```
+
+/// 定时周期的最大值（节拍数）
+/// 到期判断按有符号差值处理节拍计数回绕，周期不能超过 `i32::MAX`
+pub const MAX_TICKS: u32 = i32::MAX as u32;
+
+/// 定时器回调函数
+/// 由 [`Expired::run`] 在节拍中断中执行，应尽量简短
+pub type Callback = fn();
+
+/// 定时器模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// 单次定时器，到期后自动停止
+    OneShot,
+    /// 周期定时器，到期后按原周期重新装载
+    Periodic,
+}
+
+/// 定时器句柄
+/// 包含槽位序号和版本号，定时器被取消后旧句柄将失效
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerId {
+    index: u8,
+    generation: u8,
+}
+
+/// 软件定时器错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// 没有空闲的定时器槽位
+    NoFreeSlot,
+    /// 句柄无效或定时器已停止
+    InvalidId,
+    /// 定时周期不能为 0
+    ZeroPeriod,
+    /// 定时周期超过 [`MAX_TICKS`]
+    PeriodTooLong,
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+    /// 槽位是否已被占用
+    used: bool,
+    /// 定时器是否正在运行
+    active: bool,
+    /// 到期标志
+    expired: bool,
+    generation: u8,
+    mode: Mode,
+    /// 周期（节拍数）
+    period: u32,
+    /// 到期时刻（节拍数）
+    deadline: u32,
+    callback: Option<Callback>,
+}
+
+impl Slot {
+    const EMPTY: Slot = Slot {
+        used: false,
+        active: false,
+        expired: false,
+        generation: 0,
+        mode: Mode::OneShot,
+        period: 0,
+        deadline: 0,
+        callback: None,
+    };
+}
+
+/// 软件定时器服务
+/// `N` 为可同时注册的定时器数量
+pub struct SoftTimers<const N: usize> {
+    slots: [Slot; N],
+    /// 当前节拍计数
+    now: u32,
+}
+
+impl<const N: usize> Default for SoftTimers<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> SoftTimers<N> {
+    /// 槽位序号保存在 `u8` 中，定时器数量不能超过 256
+    const CAPACITY_CHECK: () = assert!(N <= u8::MAX as usize + 1, "too many soft timers");
+
+    /// 创建软件定时器服务，可用于 `static` 初始化
+    pub const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::CAPACITY_CHECK;
+        SoftTimers {
+            slots: [Slot::EMPTY; N],
+            now: 0,
+        }
+    }
+
+    /// 当前节拍计数
+    pub fn now(&self) -> u32 {
+        self.now
+    }
+
+    /// 注册并启动一个定时器
+    /// `ticks` 为定时周期（节拍数，1~[`MAX_TICKS`]），`callback` 为到期时在中断中调用的函数
+    pub fn start(
+        &mut self,
+        mode: Mode,
+        ticks: u32,
+        callback: Option<Callback>,
+    ) -> Result<TimerId, Error> {
+        check_period(ticks)?;
+        let index = self
+            .slots
+            .iter()
+            .position(|slot| !slot.used)
+            .ok_or(Error::NoFreeSlot)?;
+
+        let now = self.now;
+        let slot = &mut self.slots[index];
+        slot.used = true;
+        slot.active = true;
+        slot.expired = false;
+        slot.mode = mode;
+        slot.period = ticks;
+        slot.deadline = now.wrapping_add(ticks);
+        slot.callback = callback;
+
+        Ok(TimerId {
+            index: index as u8,
+            generation: slot.generation,
+        })
+    }
+
+    /// 启动一个单次定时器
+    /// 到期后定时器停止，但仍占用槽位，以便读取到期标志或用 `reschedule` 重新启动；
+    /// 不再使用时必须调用 `cancel` 释放槽位，否则反复启动会耗尽槽位并返回 [`Error::NoFreeSlot`]
+    pub fn start_one_shot(
+        &mut self,
+        ticks: u32,
+        callback: Option<Callback>,
+    ) -> Result<TimerId, Error> {
+        self.start(Mode::OneShot, ticks, callback)
+    }
+
+    /// 启动一个周期定时器
+    pub fn start_periodic(
+        &mut self,
+        ticks: u32,
+        callback: Option<Callback>,
+    ) -> Result<TimerId, Error> {
+        self.start(Mode::Periodic, ticks, callback)
+    }
+
+    /// 取消定时器并释放槽位，旧句柄随之失效
+    pub fn cancel(&mut self, id: TimerId) -> Result<(), Error> {
+        let slot = self.slot_mut(id)?;
+        *slot = Slot {
+            generation: slot.generation.wrapping_add(1),
+            ..Slot::EMPTY
+        };
+        Ok(())
+    }
+
+    /// 重新调度定时器
+    /// 以新的周期从当前节拍重新开始计时，并清除到期标志；已停止的单次定时器也可以重新启动
+    pub fn reschedule(&mut self, id: TimerId, ticks: u32) -> Result<(), Error> {
+        check_period(ticks)?;
+        let now = self.now;
+        let slot = self.slot_mut(id)?;
+        slot.active = true;
+        slot.expired = false;
+        slot.period = ticks;
+        slot.deadline = now.wrapping_add(ticks);
+        Ok(())
+    }
+
+    /// 定时器是否正在运行
+    pub fn is_active(&self, id: TimerId) -> bool {
+        self.slot(id).map(|slot| slot.active).unwrap_or(false)
+    }
+
+    /// 距离到期还剩多少节拍，定时器已停止时返回 `None`
+    pub fn remaining(&self, id: TimerId) -> Option<u32> {
+        let slot = self.slot(id).ok()?;
+        if !slot.active {
+            return None;
+        }
+        Some(slot.deadline.wrapping_sub(self.now))
+    }
+
+    /// 读取并清除到期标志
+    /// 适用于不注册回调、在主循环中轮询的场景
+    pub fn take_expired(&mut self, id: TimerId) -> bool {
+        match self.slot_mut(id) {
+            Ok(slot) => core::mem::replace(&mut slot.expired, false),
+            Err(_) => false,
+        }
+    }
+
+    /// 推进一个节拍
+    /// 在 SysTick 或 TIMx 更新中断中调用，到期的定时器会置位到期标志；
+    /// 到期定时器的回调不在这里执行，而是收集后返回，由调用者在释放借用后执行，
+    /// 因此回调中可以再次访问定时器服务（启动、取消、重新调度）
+    #[must_use = "expired callbacks must be run with `Expired::run`"]
+    pub fn tick(&mut self) -> Expired<N> {
+        self.now = self.now.wrapping_add(1);
+        let now = self.now;
+        let mut expired = Expired::new();
+
+        for slot in self.slots.iter_mut() {
+            if !slot.active || !is_due(now, slot.deadline) {
+                continue;
+            }
+
+            slot.expired = true;
+            match slot.mode {
+                Mode::OneShot => slot.active = false,
+                Mode::Periodic => slot.deadline = slot.deadline.wrapping_add(slot.period),
+            }
+
+            if let Some(callback) = slot.callback {
+                expired.push(callback);
+            }
+        }
+        expired
+    }
+
+    fn slot(&self, id: TimerId) -> Result<&Slot, Error> {
+        match self.slots.get(id.index as usize) {
+            Some(slot) if slot.used && slot.generation == id.generation => Ok(slot),
+            _ => Err(Error::InvalidId),
+        }
+    }
+
+    fn slot_mut(&mut self, id: TimerId) -> Result<&mut Slot, Error> {
+        match self.slots.get_mut(id.index as usize) {
+            Some(slot) if slot.used && slot.generation == id.generation => Ok(slot),
+            _ => Err(Error::InvalidId),
+        }
+    }
+}
+
+/// 一个节拍内到期的定时器回调
+pub struct Expired<const N: usize> {
+    callbacks: [Option<Callback>; N],
+    len: usize,
+}
+
+impl<const N: usize> Expired<N> {
+    const fn new() -> Self {
+        Expired {
+            callbacks: [None; N],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, callback: Callback) {
+        self.callbacks[self.len] = Some(callback);
+        self.len += 1;
+    }
+
+    /// 到期的回调数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 是否没有到期的回调
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 按槽位顺序执行到期的回调
+    pub fn run(self) {
+        for callback in self.callbacks.iter().take(self.len).flatten() {
+            callback();
+        }
+    }
+}
+
+/// 检查定时周期
+fn check_period(ticks: u32) -> Result<(), Error> {
+    match ticks {
+        0 => Err(Error::ZeroPeriod),
+        _ if ticks > MAX_TICKS => Err(Error::PeriodTooLong),
+        _ => Ok(()),
+    }
+}
+
+/// 判断是否已到期，兼容节拍计数回绕
+fn is_due(now: u32, deadline: u32) -> bool {
+    now.wrapping_sub(deadline) as i32 >= 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    fn count_call() {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn advance<const N: usize>(timers: &mut SoftTimers<N>, ticks: u32) {
+        for _ in 0..ticks {
+            timers.tick().run();
+        }
+    }
+
+    #[test]
+    fn due_across_wraparound() {
+        assert!(is_due(5, 5));
+        assert!(!is_due(4, 5));
+        // 到期时刻在回绕之后
+        let deadline = (u32::MAX - 1).wrapping_add(3);
+        assert!(!is_due(u32::MAX, deadline));
+        assert!(is_due(deadline, deadline));
+        // 当前节拍已回绕，到期时刻在回绕之前
+        assert!(is_due(1, u32::MAX - 1));
+    }
+
+    #[test]
+    fn periodic_rearms() {
+        let mut timers: SoftTimers<2> = SoftTimers::new();
+        let id = timers.start_periodic(3, None).unwrap();
+
+        advance(&mut timers, 2);
+        assert!(!timers.take_expired(id));
+        advance(&mut timers, 1);
+        assert!(timers.take_expired(id));
+        assert!(!timers.take_expired(id));
+        assert_eq!(timers.remaining(id), Some(3));
+
+        advance(&mut timers, 3);
+        assert!(timers.take_expired(id));
+        assert!(timers.is_active(id));
+    }
+
+    #[test]
+    fn one_shot_stops_and_reschedules() {
+        let mut timers: SoftTimers<1> = SoftTimers::new();
+        let id = timers.start_one_shot(2, None).unwrap();
+
+        advance(&mut timers, 2);
+        assert!(timers.take_expired(id));
+        assert!(!timers.is_active(id));
+        assert_eq!(timers.remaining(id), None);
+
+        timers.reschedule(id, 1).unwrap();
+        advance(&mut timers, 1);
+        assert!(timers.take_expired(id));
+    }
+
+    #[test]
+    fn cancel_invalidates_old_id() {
+        let mut timers: SoftTimers<1> = SoftTimers::new();
+        let old = timers.start_periodic(10, None).unwrap();
+        timers.cancel(old).unwrap();
+
+        assert_eq!(timers.cancel(old), Err(Error::InvalidId));
+        assert_eq!(timers.reschedule(old, 5), Err(Error::InvalidId));
+        assert!(!timers.is_active(old));
+
+        // 槽位被复用，旧句柄仍然无效
+        let new = timers.start_periodic(10, None).unwrap();
+        assert_ne!(old, new);
+        assert!(timers.is_active(new));
+        assert_eq!(timers.cancel(old), Err(Error::InvalidId));
+    }
+
+    #[test]
+    fn slots_exhausted() {
+        let mut timers: SoftTimers<2> = SoftTimers::new();
+        let first = timers.start_one_shot(1, None).unwrap();
+        timers.start_one_shot(1, None).unwrap();
+        assert_eq!(timers.start_one_shot(1, None), Err(Error::NoFreeSlot));
+
+        // 到期的单次定时器仍占用槽位，取消后才能复用
+        advance(&mut timers, 1);
+        assert_eq!(timers.start_one_shot(1, None), Err(Error::NoFreeSlot));
+        timers.cancel(first).unwrap();
+        assert!(timers.start_one_shot(1, None).is_ok());
+
+        assert_eq!(timers.start_one_shot(0, None), Err(Error::ZeroPeriod));
+    }
+
+    #[test]
+    fn period_limit() {
+        let mut timers: SoftTimers<2> = SoftTimers::new();
+        assert_eq!(
+            timers.start_one_shot(MAX_TICKS + 1, None),
+            Err(Error::PeriodTooLong)
+        );
+        assert_eq!(
+            timers.start_periodic(u32::MAX, None),
+            Err(Error::PeriodTooLong)
+        );
+
+        // 最长周期不会立即到期
+        let id = timers.start_one_shot(MAX_TICKS, None).unwrap();
+        advance(&mut timers, 1000);
+        assert!(timers.is_active(id));
+        assert_eq!(
+            timers.reschedule(id, MAX_TICKS + 1),
+            Err(Error::PeriodTooLong)
+        );
+        assert!(timers.is_active(id));
+    }
+
+    #[test]
+    fn callbacks_returned_from_tick() {
+        let mut timers: SoftTimers<2> = SoftTimers::new();
+        timers.start_periodic(1, Some(count_call)).unwrap();
+        timers.start_periodic(2, Some(count_call)).unwrap();
+
+        let before = CALLS.load(Ordering::Relaxed);
+        let expired = timers.tick();
+        assert_eq!(expired.len(), 1);
+        // 回调在 run 中执行
+        assert_eq!(CALLS.load(Ordering::Relaxed), before);
+        expired.run();
+        assert_eq!(CALLS.load(Ordering::Relaxed), before + 1);
+
+        let expired = timers.tick();
+        assert_eq!(expired.len(), 2);
+        expired.run();
+        assert_eq!(CALLS.load(Ordering::Relaxed), before + 3);
+    }
+}